@@ -1,10 +1,13 @@
 use derive_more::derive::Constructor;
 use thiserror::Error;
 
-use crate::util::{
-    actions::{Actions, Error as ActionError},
-    dir::LabeledDir,
-    prompting::{Prompter, PrompterError},
+use crate::{
+    mapping::Permissions,
+    util::{
+        actions::{Actions, Error as ActionError},
+        dir::LabeledDir,
+        prompting::{Prompter, PrompterError},
+    },
 };
 
 #[derive(Debug, Error)]
@@ -25,6 +28,8 @@ pub type Result<T> = core::result::Result<T, DirectoryCreatorError>;
 pub struct DirectoryCreator<'a, A: Actions, PR: Prompter> {
     actions: &'a A,
     prompter: &'a PR,
+    // Left to the umask when not set
+    permissions: Option<Permissions>,
 }
 
 impl<A: Actions, PR: Prompter> DirectoryCreator<'_, A, PR> {
@@ -35,6 +40,9 @@ impl<A: Actions, PR: Prompter> DirectoryCreator<'_, A, PR> {
                 .confirm(format!("Create {} directory at {}", D::LABEL, dir), false)?
         {
             self.actions.make_dir(dir)?;
+            if let Some(permissions) = self.permissions {
+                self.actions.set_permissions(dir, permissions.0)?;
+            }
             Ok(())
         } else {
             Err(DirectoryCreatorError::DeclinedToCreate)
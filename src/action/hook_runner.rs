@@ -0,0 +1,187 @@
+use derive_more::derive::{Constructor, Display};
+use log::debug;
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{
+    components::{environment::types::Environment, linker::types::DotLink},
+    config::spec::types::Hooks,
+    util::actions::{Actions, Error as ActionError},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum HookKind {
+    #[display("pre_sync")]
+    PreSync,
+
+    #[display("post_sync")]
+    PostSync,
+
+    #[display("on_link")]
+    OnLink,
+}
+
+impl HookKind {
+    pub fn commands(self, hooks: &Hooks) -> &[String] {
+        match self {
+            HookKind::PreSync => &hooks.pre_sync,
+            HookKind::PostSync => &hooks.post_sync,
+            HookKind::OnLink => &hooks.on_link,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum HookRunnerError {
+    #[error("{kind} hook in {} failed: {source}", dir.display())]
+    Hook {
+        kind: HookKind,
+        dir: PathBuf,
+        source: ActionError,
+    },
+}
+
+pub type Result<T> = core::result::Result<T, HookRunnerError>;
+
+#[derive(Debug, Constructor)]
+pub struct HookRunner<'a, A: Actions> {
+    actions: &'a A,
+    environment: &'a Environment,
+}
+
+impl<A: Actions> HookRunner<'_, A> {
+    /// Runs the hooks of a kind in their directory. On link hooks also get the link in their
+    /// environment.
+    pub fn run(
+        &self,
+        kind: HookKind,
+        dir: &Path,
+        hooks: &Hooks,
+        link: Option<&DotLink>,
+    ) -> Result<()> {
+        let mut env: Vec<(&str, &OsStr)> = vec![
+            ("DOTZO_HOME", self.environment.home.as_ref().as_os_str()),
+            ("DOTZO_CONFIG", self.environment.config.as_ref().as_os_str()),
+        ];
+        if let Some(link) = link {
+            env.push(("DOTZO_TARGET", link.target.as_os_str()));
+            env.push(("DOTZO_SOURCE", link.source.as_os_str()));
+        }
+
+        for command in kind.commands(hooks) {
+            debug!("Running {} hook in {}: {}", kind, dir.display(), command);
+            self.actions
+                .run_command(command, dir, &env)
+                .map_err(|source| HookRunnerError::Hook {
+                    kind,
+                    dir: dir.to_owned(),
+                    source,
+                })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, ffi::OsString, sync::LazyLock};
+
+    use super::*;
+    use crate::{
+        mapping::LinkMode,
+        util::{actions::testing::TestActions, fs::testing::TestFs},
+    };
+
+    static TEST_ENVIRONMENT: LazyLock<Environment> = LazyLock::new(|| {
+        Environment::new(
+            PathBuf::from("/home").into(),
+            PathBuf::from("/home/.config").into(),
+            PathBuf::from("/home/.local/share").into(),
+            PathBuf::from("/home/.local/state").into(),
+            PathBuf::from("/home/.cache").into(),
+        )
+    });
+
+    #[test]
+    fn test_runs_only_hooks_of_kind() {
+        let actions = TestActions::new(RefCell::new(TestFs::new([])));
+        let runner = HookRunner::new(&actions, &TEST_ENVIRONMENT);
+        let hooks = Hooks {
+            pre_sync: vec!["mkdir -p cache".into()],
+            post_sync: vec!["fc-cache".into(), "echo done".into()],
+            on_link: vec![],
+        };
+        let dir = PathBuf::from("/home/_/etc/fonts");
+
+        runner.run(HookKind::PostSync, &dir, &hooks, None).unwrap();
+        runner
+            .run(
+                HookKind::OnLink,
+                &dir,
+                &hooks,
+                Some(&DotLink::new(
+                    dir.join("font.ttf"),
+                    PathBuf::from("/home/.fonts"),
+                    PathBuf::from("_/etc/fonts/font.ttf"),
+                    LinkMode::Symlink,
+                    None,
+                )),
+            )
+            .unwrap();
+
+        let sync_env = vec![
+            ("DOTZO_HOME".to_string(), OsString::from("/home")),
+            ("DOTZO_CONFIG".to_string(), OsString::from("/home/.config")),
+        ];
+        assert_eq!(
+            *actions.commands.borrow(),
+            vec![
+                ("fc-cache".to_string(), dir.clone(), sync_env.clone()),
+                ("echo done".to_string(), dir.clone(), sync_env),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_on_link_environment() {
+        let actions = TestActions::new(RefCell::new(TestFs::new([])));
+        let runner = HookRunner::new(&actions, &TEST_ENVIRONMENT);
+        let hooks = Hooks {
+            on_link: vec!["tmux source-file ~/.tmux.conf".into()],
+            ..Default::default()
+        };
+        let dir = PathBuf::from("/home/_/etc");
+        let link = DotLink::new(
+            dir.join("tmux.conf"),
+            PathBuf::from("/home/.tmux.conf"),
+            PathBuf::from("_/etc/tmux.conf"),
+            LinkMode::Symlink,
+            None,
+        );
+
+        runner
+            .run(HookKind::OnLink, &dir, &hooks, Some(&link))
+            .unwrap();
+
+        let commands = actions.commands.borrow();
+        let (_, _, env) = &commands[0];
+        assert_eq!(
+            *env,
+            vec![
+                ("DOTZO_HOME".to_string(), OsString::from("/home")),
+                ("DOTZO_CONFIG".to_string(), OsString::from("/home/.config")),
+                (
+                    "DOTZO_TARGET".to_string(),
+                    OsString::from("/home/.tmux.conf")
+                ),
+                (
+                    "DOTZO_SOURCE".to_string(),
+                    OsString::from("/home/_/etc/tmux.conf")
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use clap::ValueEnum;
+use derive_more::derive::Constructor;
+use log::{info, warn};
+use thiserror::Error;
+
+use crate::{
+    components::linker::types::DotLink,
+    util::prompting::{Prompter, PrompterError},
+};
+
+use super::make_link::LinkChange;
+
+pub type LinkChanges = Vec<(LinkChange, DotLink)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ClobberPolicy {
+    /// Move the file aside and link, or overwrite a changed secret
+    Backup,
+    /// Leave the file
+    Skip,
+    /// Stop before changing anything
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WrongLinkPolicy {
+    /// Replace the link
+    Fix,
+    /// Leave the link
+    Skip,
+    /// Stop before changing anything
+    Fail,
+}
+
+/// Answers for changes that are decided without asking, the rest are asked about
+#[derive(Debug, Clone, Copy, Default, Constructor)]
+pub struct LinkPolicy {
+    pub on_clobber: Option<ClobberPolicy>,
+    pub on_wrong_link: Option<WrongLinkPolicy>,
+    // Create new links and set permissions without asking
+    pub create: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+    Apply,
+    Skip,
+    Fail,
+    Ask { default: bool },
+}
+
+impl LinkPolicy {
+    fn decide(&self, change: LinkChange) -> Decision {
+        match change {
+            // Relinking loses nothing, since the copy has the source's contents
+            LinkChange::Create | LinkChange::Permissions | LinkChange::Relink if self.create => {
+                Decision::Apply
+            }
+            LinkChange::Create | LinkChange::Permissions | LinkChange::Relink => {
+                Decision::Ask { default: true }
+            }
+            // Secrets come from the repo, so a changed copy is overwritten unless told otherwise
+            LinkChange::Overwrite => match self.on_clobber {
+                Some(ClobberPolicy::Backup) => Decision::Apply,
+                Some(ClobberPolicy::Skip) => Decision::Skip,
+                Some(ClobberPolicy::Fail) => Decision::Fail,
+                None => Decision::Ask { default: true },
+            },
+            LinkChange::Backup => match self.on_clobber {
+                Some(ClobberPolicy::Backup) => Decision::Apply,
+                Some(ClobberPolicy::Skip) => Decision::Skip,
+                Some(ClobberPolicy::Fail) => Decision::Fail,
+                // Backups start unticked, since they move files
+                None => Decision::Ask { default: false },
+            },
+            LinkChange::Fix => match self.on_wrong_link {
+                Some(WrongLinkPolicy::Fix) => Decision::Apply,
+                Some(WrongLinkPolicy::Skip) => Decision::Skip,
+                Some(WrongLinkPolicy::Fail) => Decision::Fail,
+                None => Decision::Ask { default: true },
+            },
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LinkSelectorError {
+    #[error("Prompt error")]
+    Prompt(#[from] PrompterError),
+
+    #[error("A file is in the way of {}", .0.display())]
+    Clobber(PathBuf),
+
+    #[error("{} links somewhere else", .0.display())]
+    WrongLink(PathBuf),
+}
+
+pub type Result<T> = core::result::Result<T, LinkSelectorError>;
+
+#[derive(Debug, Constructor)]
+pub struct LinkSelector<'a, PR: Prompter> {
+    prompter: &'a PR,
+    policy: LinkPolicy,
+}
+
+impl<PR: Prompter> LinkSelector<'_, PR> {
+    /// Decides the changes the policy covers, and lets the user tick which of the rest to apply
+    pub fn select(&self, mut changes: LinkChanges) -> Result<LinkChanges> {
+        changes.sort_by(|(_, a), (_, b)| a.target.cmp(&b.target));
+
+        let mut selected = LinkChanges::new();
+        let mut asked = LinkChanges::new();
+        let mut defaults = Vec::new();
+        for (change, link) in changes {
+            match self.policy.decide(change) {
+                Decision::Apply => selected.push((change, link)),
+                Decision::Skip => info!("Skipping {}: {}", change, link.target.display()),
+                Decision::Fail if matches!(change, LinkChange::Backup | LinkChange::Overwrite) => {
+                    return Err(LinkSelectorError::Clobber(link.target))
+                }
+                Decision::Fail => return Err(LinkSelectorError::WrongLink(link.target)),
+                Decision::Ask { default } => {
+                    if default {
+                        defaults.push(asked.len());
+                    }
+                    asked.push((change, link));
+                }
+            }
+        }
+
+        if !asked.is_empty() {
+            let options: Vec<String> = asked
+                .iter()
+                .map(|(change, link)| format!("{}: {}", change, link.target.display()))
+                .collect();
+            let chosen: HashSet<usize> = self
+                .prompter
+                .multi_select("Select the links to apply", &options, &defaults)?
+                .into_iter()
+                .collect();
+            for (i, (change, link)) in asked.into_iter().enumerate() {
+                if chosen.contains(&i) {
+                    selected.push((change, link));
+                } else if change == LinkChange::Backup {
+                    // Unticked by default, so --yes and --no-input leave these too
+                    warn!(
+                        "Left the file in the way of {}, --on-clobber backup moves it aside",
+                        link.target.display()
+                    );
+                }
+            }
+            selected.sort_by(|(_, a), (_, b)| a.target.cmp(&b.target));
+        }
+        Ok(selected)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        mapping::LinkMode,
+        util::prompting::testing::{Answer, ScriptedPrompter},
+    };
+
+    fn change(change: LinkChange, target: &str) -> (LinkChange, DotLink) {
+        let link = DotLink::new(
+            PathBuf::from("_/etc").join(target),
+            PathBuf::from("home").join(target),
+            PathBuf::from("../_/etc").join(target),
+            LinkMode::Symlink,
+            None,
+        );
+        (change, link)
+    }
+
+    #[test]
+    fn test_select_in_target_order() {
+        let prompter = ScriptedPrompter::new([Answer::Select(vec![0, 2])]);
+        let selector = LinkSelector::new(&prompter, LinkPolicy::default());
+
+        let selected = selector
+            .select(vec![
+                change(LinkChange::Fix, "c"),
+                change(LinkChange::Create, "a"),
+                change(LinkChange::Backup, "b"),
+            ])
+            .unwrap();
+        assert_eq!(
+            selected,
+            vec![
+                change(LinkChange::Create, "a"),
+                change(LinkChange::Fix, "c")
+            ]
+        );
+        assert_eq!(prompter.asked.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_unscripted_prompt_fails() {
+        let prompter = ScriptedPrompter::new([Answer::Confirm(true)]);
+        let selector = LinkSelector::new(&prompter, LinkPolicy::default());
+
+        assert!(selector
+            .select(vec![change(LinkChange::Create, "a")])
+            .is_err());
+    }
+
+    #[test]
+    fn test_policy_decides_without_asking() {
+        let prompter = ScriptedPrompter::new([]);
+        let policy = LinkPolicy::new(
+            Some(ClobberPolicy::Backup),
+            Some(WrongLinkPolicy::Skip),
+            false,
+        );
+        let selector = LinkSelector::new(&prompter, policy);
+
+        let selected = selector
+            .select(vec![
+                change(LinkChange::Fix, "c"),
+                change(LinkChange::Backup, "b"),
+            ])
+            .unwrap();
+        assert_eq!(selected, vec![change(LinkChange::Backup, "b")]);
+        assert!(prompter.asked.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_fail_policy() {
+        let prompter = ScriptedPrompter::new([]);
+        let policy = LinkPolicy::new(Some(ClobberPolicy::Fail), None, false);
+        let selector = LinkSelector::new(&prompter, policy);
+
+        assert!(matches!(
+            selector.select(vec![
+                change(LinkChange::Create, "a"),
+                change(LinkChange::Backup, "b"),
+            ]),
+            Err(LinkSelectorError::Clobber(target)) if target == Path::new("home/b")
+        ));
+    }
+
+    #[test]
+    fn test_create_policy() {
+        let prompter = ScriptedPrompter::new([Answer::Select(vec![])]);
+        let policy = LinkPolicy::new(None, None, true);
+        let selector = LinkSelector::new(&prompter, policy);
+
+        let selected = selector
+            .select(vec![
+                change(LinkChange::Create, "a"),
+                change(LinkChange::Backup, "b"),
+                change(LinkChange::Permissions, "c"),
+            ])
+            .unwrap();
+        assert_eq!(
+            selected,
+            vec![
+                change(LinkChange::Create, "a"),
+                change(LinkChange::Permissions, "c")
+            ]
+        );
+        assert_eq!(prompter.asked.borrow().len(), 1);
+    }
+}
@@ -1,14 +1,19 @@
-use std::io::ErrorKind;
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
 
-use derive_more::derive::Constructor;
+use derive_more::derive::{Constructor, Display};
 use log::{debug, info};
 use thiserror::Error;
 
 use crate::{
     components::linker::types::DotLink,
+    mapping::LinkMode,
     util::{
         actions::{Actions, Error as ActionError},
         fs::{LinkReader, MetadataChecks},
+        secret::{digest, Vault, VaultError},
     },
 };
 
@@ -17,39 +22,184 @@ pub enum LinkCreatorError {
     #[error("Action error")]
     Action(#[from] ActionError),
 
+    #[error("{} is a secret, but no secret_key is set in the rc file", .0.display())]
+    NoSecretKey(PathBuf),
+
+    #[error("Secret error: {0}")]
+    Vault(#[from] VaultError),
+
+    #[error("Can't hard link {} to {}, they are on different filesystems", .target.display(), .original.display())]
+    CrossDevice { original: PathBuf, target: PathBuf },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
 pub type Result<T> = core::result::Result<T, LinkCreatorError>;
 
+/// How a link gets applied, depending on what is already at the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum LinkChange {
+    #[display("create")]
+    Create,
+
+    // A file is in the way and gets moved aside
+    #[display("back up and replace")]
+    Backup,
+
+    // A link pointing elsewhere is in the way and gets removed
+    #[display("fix link")]
+    Fix,
+
+    // The link is in place, only its permissions change
+    #[display("set permissions")]
+    Permissions,
+
+    // A copy with the same contents is in the way of a hard link and gets replaced
+    #[display("relink")]
+    Relink,
+
+    // A decrypted copy differs from its secret and gets replaced, without a plaintext backup
+    #[display("overwrite secret")]
+    Overwrite,
+}
+
 #[derive(Debug, Constructor)]
 pub struct LinkCreator<'a, MC: MetadataChecks, LR: LinkReader, A: Actions> {
     metadata_checks: &'a MC,
     link_reader: &'a LR,
     actions: &'a A,
+    // Only needed for secret mappings
+    vault: Option<&'a Vault>,
 }
 
 impl<MC: MetadataChecks, LR: LinkReader, A: Actions> LinkCreator<'_, MC, LR, A> {
-    pub fn create(&self, DotLink { target, link }: &DotLink) -> Result<bool> {
-        let link_path = &link.to_path("");
+    fn is_taken(&self, path: &Path) -> bool {
+        self.metadata_checks.exists(path) || self.metadata_checks.is_symlink(path)
+    }
+
+    // The first free path of the form <target><suffix>[.n]
+    fn free_path(&self, target: &Path, suffix: &str) -> PathBuf {
+        let mut name = target.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        let mut backup = target.with_file_name(&name);
+        let mut count = 1;
+        while self.is_taken(&backup) {
+            let mut numbered = name.clone();
+            numbered.push(format!(".{}", count));
+            backup = target.with_file_name(numbered);
+            count += 1;
+        }
+        backup
+    }
+
+    // Gives the target the declared permissions, if any
+    fn set_permissions(&self, dot_link: &DotLink) -> Result<()> {
+        if let Some(permissions) = dot_link.permissions {
+            self.actions
+                .set_permissions(&dot_link.target, permissions.0)?;
+        }
+        Ok(())
+    }
+
+    /// Clears whatever is in the way of the change, then creates the link and sets its
+    /// permissions
+    pub fn apply(&self, change: LinkChange, dot_link: &DotLink) -> Result<bool> {
+        let target = &dot_link.target;
+        match change {
+            LinkChange::Permissions => return self.set_permissions(dot_link).map(|_| true),
+            LinkChange::Create => (),
+            LinkChange::Relink => return self.relink(dot_link),
+            LinkChange::Overwrite => return self.overwrite_secret(dot_link),
+            LinkChange::Backup => {
+                let backup = self.free_path(target, ".dotzo-backup");
+                self.actions.rename(target, &backup)?;
+                info!(
+                    path:% = target.display(), backup:% = backup.display();
+                    "Backed up {} to {}",
+                    target.display(),
+                    backup.display()
+                );
+            }
+            LinkChange::Fix => self.actions.remove_link(target)?,
+        }
+        let created = self.create(dot_link)?;
+        if created {
+            self.set_permissions(dot_link)?;
+        }
+        Ok(created)
+    }
+
+    // Hard links next to the target and moves the new link over it, so the target is never
+    // missing
+    fn relink(&self, dot_link: &DotLink) -> Result<bool> {
+        let DotLink { target, link, .. } = dot_link;
+        let temporary = self.free_path(target, ".dotzo-relink");
+        self.actions.hard_link(&temporary, link)?;
+        self.actions.rename(&temporary, target)?;
+        info!(
+            path:% = target.display(), link:% = link.display();
+            "Relinked {} => {}",
+            target.display(),
+            link.display()
+        );
+        self.set_permissions(dot_link)?;
+        Ok(true)
+    }
+
+    // Writes the decrypted copy next to the target and moves it over, so the secret is never
+    // missing and no plaintext is left behind
+    fn overwrite_secret(&self, dot_link: &DotLink) -> Result<bool> {
+        let DotLink { target, link, .. } = dot_link;
+        let temporary = self.free_path(target, ".dotzo-secret");
+        self.actions
+            .write_private(&temporary, &self.decrypt(link)?)?;
+        self.actions.rename(&temporary, target)?;
+        info!(
+            path:% = target.display(), link:% = link.display();
+            "Decrypted {} over {}",
+            link.display(),
+            target.display()
+        );
+        self.set_permissions(dot_link)?;
+        Ok(true)
+    }
+
+    fn decrypt(&self, link: &Path) -> Result<Vec<u8>> {
+        let vault = self
+            .vault
+            .ok_or_else(|| LinkCreatorError::NoSecretKey(link.to_owned()))?;
+        Ok(vault.decrypt(&self.link_reader.read_file(link)?)?)
+    }
+
+    pub fn create(&self, dot_link: &DotLink) -> Result<bool> {
+        match dot_link.mode {
+            LinkMode::Symlink => self.create_symlink(dot_link),
+            LinkMode::Hardlink => self.create_hard_link(dot_link),
+            LinkMode::Secret => self.create_secret(dot_link),
+        }
+    }
 
+    fn create_symlink(&self, DotLink { target, link, .. }: &DotLink) -> Result<bool> {
         debug!(
             "Attempting to make link {} => {}",
             target.display(),
-            link_path.display()
+            link.display()
         );
 
-        match self
-            .actions
-            .symlink(target, link_path)
-            .inspect(|_| info!("Linked {} => {}", target.display(), link_path.display()))
-        {
+        match self.actions.symlink(target, link).inspect(|_| {
+            info!(
+                path:% = target.display(), link:% = link.display();
+                "Linked {} => {}",
+                target.display(),
+                link.display()
+            )
+        }) {
             Ok(()) => Ok(true),
             Err(ActionError::Io(ioe)) if ioe.kind() == ErrorKind::AlreadyExists => {
                 if self.metadata_checks.is_symlink(target) {
                     let current_link = self.link_reader.read_link(target)?;
-                    if &current_link == link_path {
+                    if &current_link == link {
                         debug!(
                             "Link {} alread exists pointing to {}",
                             target.display(),
@@ -72,4 +222,354 @@ impl<MC: MetadataChecks, LR: LinkReader, A: Actions> LinkCreator<'_, MC, LR, A>
             Err(e) => Err(e.into()),
         }
     }
+
+    fn create_secret(&self, DotLink { target, link, .. }: &DotLink) -> Result<bool> {
+        debug!(
+            "Attempting to decrypt {} => {}",
+            target.display(),
+            link.display()
+        );
+
+        let contents = self.decrypt(link)?;
+        match self.actions.write_private(target, &contents).inspect(|_| {
+            info!(
+                path:% = target.display(), link:% = link.display();
+                "Decrypted {} => {}",
+                target.display(),
+                link.display()
+            )
+        }) {
+            Ok(()) => Ok(true),
+            Err(ActionError::Io(ioe))
+                if ioe.kind() == ErrorKind::AlreadyExists
+                    && self.metadata_checks.is_file(target)
+                    && digest(&self.link_reader.read_file(target)?) == digest(&contents) =>
+            {
+                debug!("Secret {} is already decrypted", target.display());
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn create_hard_link(&self, DotLink { target, link, .. }: &DotLink) -> Result<bool> {
+        debug!(
+            "Attempting to make hard link {} => {}",
+            target.display(),
+            link.display()
+        );
+
+        // Checked up front, since the error from the OS doesn't name either path
+        let directory = target.parent().unwrap_or(target);
+        if self.metadata_checks.device(link)? != self.metadata_checks.device(directory)? {
+            return Err(LinkCreatorError::CrossDevice {
+                original: link.clone(),
+                target: target.clone(),
+            });
+        }
+
+        match self.actions.hard_link(target, link).inspect(|_| {
+            info!(
+                path:% = target.display(), link:% = link.display();
+                "Hard linked {} => {}",
+                target.display(),
+                link.display()
+            )
+        }) {
+            Ok(()) => Ok(true),
+            Err(ActionError::Io(ioe))
+                if ioe.kind() == ErrorKind::AlreadyExists
+                    && !self.metadata_checks.is_symlink(target)
+                    && self.metadata_checks.inode(target)?
+                        == self.metadata_checks.inode(link)? =>
+            {
+                debug!("Hard link {} already exists", target.display());
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use super::*;
+    use age::x25519::Identity;
+
+    use crate::{
+        mapping::Permissions,
+        util::{
+            actions::testing::TestActions,
+            fs::testing::{TestFile, TestFs},
+        },
+    };
+
+    fn test_fs() -> TestFs {
+        TestFs::new([
+            (PathBuf::from("../_/etc/vimrc"), TestFile::Regular),
+            (PathBuf::from("home/.bashrc"), TestFile::Regular),
+            (
+                PathBuf::from("home/.vimrc"),
+                TestFile::Symlink(PathBuf::from("../_/etc/vimrc")),
+            ),
+        ])
+    }
+
+    fn dot_link(target: &str, link: &str) -> DotLink {
+        DotLink::new(
+            PathBuf::from(link),
+            PathBuf::from(target),
+            PathBuf::from(link),
+            LinkMode::Symlink,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_create_new_link() {
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, None);
+
+        let created = creator.create(&dot_link("home/.tmux.conf", "../_/etc/tmux.conf"));
+        assert!(matches!(created, Ok(true)));
+        assert!(actions.fs.borrow().is_symlink("home/.tmux.conf"));
+    }
+
+    #[test]
+    fn test_create_hard_link() {
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, None);
+        let hard_link = |target: &str| {
+            DotLink::new(
+                PathBuf::from("../_/etc/vimrc"),
+                PathBuf::from(target),
+                PathBuf::from("../_/etc/vimrc"),
+                LinkMode::Hardlink,
+                None,
+            )
+        };
+
+        let created = creator.create(&hard_link("home/vimrc"));
+        assert!(matches!(created, Ok(true)));
+        let fs = actions.fs.borrow();
+        assert_eq!(
+            fs.get_file(&PathBuf::from("home/vimrc")).unwrap(),
+            TestFile::Regular
+        );
+        assert_eq!(
+            fs.inode("home/vimrc").unwrap(),
+            fs.inode("../_/etc/vimrc").unwrap()
+        );
+
+        let mut elsewhere = test_fs();
+        elsewhere.devices.insert(PathBuf::from("home"), 1);
+        let creator = LinkCreator::new(&elsewhere, &elsewhere, &actions, None);
+        let created = creator.create(&hard_link("home/.gvimrc"));
+        assert!(matches!(created, Err(LinkCreatorError::CrossDevice { .. })));
+    }
+
+    #[test]
+    fn test_apply_relink() {
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, None);
+        let hard_link = DotLink::new(
+            PathBuf::from("../_/etc/vimrc"),
+            PathBuf::from("home/.bashrc"),
+            PathBuf::from("../_/etc/vimrc"),
+            LinkMode::Hardlink,
+            None,
+        );
+
+        let applied = creator.apply(LinkChange::Relink, &hard_link);
+        assert!(matches!(applied, Ok(true)));
+        let fs = actions.fs.borrow();
+        assert_eq!(
+            fs.inode("home/.bashrc").unwrap(),
+            fs.inode("../_/etc/vimrc").unwrap()
+        );
+        assert!(!fs.exists("home/.bashrc.dotzo-relink"));
+        assert!(!fs.exists("home/.bashrc.dotzo-backup"));
+    }
+
+    #[test]
+    fn test_create_existing_link() {
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, None);
+
+        let created = creator.create(&dot_link("home/.vimrc", "../_/etc/vimrc"));
+        assert!(matches!(created, Ok(false)));
+    }
+
+    #[test]
+    fn test_create_over_file() {
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, None);
+
+        let created = creator.create(&dot_link("home/.bashrc", "../_/etc/bashrc"));
+        assert!(matches!(
+            created,
+            Err(LinkCreatorError::Action(ActionError::Io(e))) if e.kind() == ErrorKind::AlreadyExists
+        ));
+    }
+
+    #[test]
+    fn test_apply_backup() {
+        let with_backup = || {
+            let mut fs = test_fs();
+            fs.add_file(
+                PathBuf::from("home/.bashrc.dotzo-backup"),
+                TestFile::Regular,
+            );
+            fs
+        };
+        let fs = with_backup();
+        let actions = TestActions::new(RefCell::new(with_backup()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, None);
+
+        let applied = creator.apply(
+            LinkChange::Backup,
+            &dot_link("home/.bashrc", "../_/etc/bashrc"),
+        );
+        assert!(matches!(applied, Ok(true)));
+        assert!(actions.fs.borrow().is_symlink("home/.bashrc"));
+        assert_eq!(
+            actions
+                .fs
+                .borrow()
+                .get_file(&PathBuf::from("home/.bashrc.dotzo-backup.1"))
+                .unwrap(),
+            TestFile::Regular
+        );
+    }
+
+    #[test]
+    fn test_apply_fix() {
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, None);
+
+        let applied = creator.apply(
+            LinkChange::Fix,
+            &dot_link("home/.vimrc", "../_/etc/vim/vimrc"),
+        );
+        assert!(matches!(applied, Ok(true)));
+        assert_eq!(
+            actions
+                .fs
+                .borrow()
+                .get_file(&PathBuf::from("home/.vimrc"))
+                .unwrap(),
+            TestFile::Symlink(PathBuf::from("../_/etc/vim/vimrc"))
+        );
+    }
+
+    #[test]
+    fn test_create_secret() {
+        let vault = Vault::new(Identity::generate());
+        let with_secret = || {
+            let mut fs = test_fs();
+            fs.add_file(PathBuf::from("../_/etc/netrc.age"), TestFile::Regular);
+            fs.contents.insert(
+                PathBuf::from("../_/etc/netrc.age"),
+                vault.encrypt(b"machine").unwrap().into_bytes(),
+            );
+            fs
+        };
+        let fs = with_secret();
+        let actions = TestActions::new(RefCell::new(with_secret()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, Some(&vault));
+        let secret = DotLink::new(
+            PathBuf::from("../_/etc/netrc.age"),
+            PathBuf::from("home/.netrc"),
+            PathBuf::from("../_/etc/netrc.age"),
+            LinkMode::Secret,
+            Some(Permissions(0o600)),
+        );
+
+        let applied = creator.apply(LinkChange::Create, &secret);
+        assert!(matches!(applied, Ok(true)));
+        let written = actions.fs.borrow();
+        assert!(written.is_file("home/.netrc"));
+        assert_eq!(written.mode("home/.netrc").unwrap(), 0o600);
+        assert_eq!(written.read_file("home/.netrc").unwrap(), b"machine");
+    }
+
+    #[test]
+    fn test_apply_overwrite_secret() {
+        let vault = Vault::new(Identity::generate());
+        let with_secret = || {
+            let mut fs = test_fs();
+            fs.add_file(PathBuf::from("../_/etc/netrc.age"), TestFile::Regular);
+            fs.contents.insert(
+                PathBuf::from("../_/etc/netrc.age"),
+                vault.encrypt(b"rotated").unwrap().into_bytes(),
+            );
+            fs.add_file(PathBuf::from("home/.netrc"), TestFile::Regular);
+            fs.contents
+                .insert(PathBuf::from("home/.netrc"), b"expired".to_vec());
+            fs
+        };
+        let fs = with_secret();
+        let actions = TestActions::new(RefCell::new(with_secret()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, Some(&vault));
+        let secret = DotLink::new(
+            PathBuf::from("../_/etc/netrc.age"),
+            PathBuf::from("home/.netrc"),
+            PathBuf::from("../_/etc/netrc.age"),
+            LinkMode::Secret,
+            Some(Permissions(0o600)),
+        );
+
+        let applied = creator.apply(LinkChange::Overwrite, &secret);
+        assert!(matches!(applied, Ok(true)));
+        let written = actions.fs.borrow();
+        assert_eq!(written.read_file("home/.netrc").unwrap(), b"rotated");
+        assert_eq!(written.mode("home/.netrc").unwrap(), 0o600);
+        assert!(!written.exists("home/.netrc.dotzo-secret"));
+        assert!(!written.exists("home/.netrc.dotzo-backup"));
+    }
+
+    #[test]
+    fn test_apply_permissions() {
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, None);
+        let mut link = dot_link("home/.vimrc", "../_/etc/vimrc");
+        link.permissions = Some(Permissions(0o600));
+
+        let applied = creator.apply(LinkChange::Permissions, &link);
+        assert!(matches!(applied, Ok(true)));
+        assert_eq!(actions.fs.borrow().mode("../_/etc/vimrc").unwrap(), 0o600);
+    }
+
+    #[test]
+    fn test_fix_refuses_files() {
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+        let creator = LinkCreator::new(&fs, &fs, &actions, None);
+
+        let applied = creator.apply(
+            LinkChange::Fix,
+            &dot_link("home/.bashrc", "../_/etc/bashrc"),
+        );
+        assert!(matches!(
+            applied,
+            Err(LinkCreatorError::Action(ActionError::Io(e))) if e.kind() == ErrorKind::InvalidInput
+        ));
+        assert_eq!(
+            actions
+                .fs
+                .borrow()
+                .get_file(&PathBuf::from("home/.bashrc"))
+                .unwrap(),
+            TestFile::Regular
+        );
+    }
 }
@@ -1,2 +1,5 @@
 pub mod directory_creator;
+pub mod hook_runner;
+pub mod link_selector;
 pub mod make_link;
+pub mod repo_cloner;
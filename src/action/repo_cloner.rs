@@ -0,0 +1,62 @@
+use derive_more::derive::Constructor;
+use log::{debug, info};
+use thiserror::Error;
+
+use crate::{
+    components::repo::types::Repo,
+    util::{
+        fs::MetadataChecks,
+        git::{Error as GitError, Git},
+        prompting::{Prompter, PrompterError},
+    },
+    validation::directory::{DirectoryCheck, DirectoryCheckError},
+};
+
+#[derive(Debug, Error)]
+pub enum RepoClonerError {
+    #[error("Git error: {0}")]
+    Git(#[from] GitError),
+
+    #[error("Prompt error")]
+    Prompt(#[from] PrompterError),
+
+    #[error("Declined to clone")]
+    DeclinedToClone,
+}
+
+pub type Result<T> = core::result::Result<T, RepoClonerError>;
+
+#[derive(Debug, Constructor)]
+pub struct RepoCloner<'a, MC: MetadataChecks, G: Git, PR: Prompter> {
+    exists: DirectoryCheck<'a, MC>,
+    git: &'a G,
+    prompter: &'a PR,
+}
+
+impl<MC: MetadataChecks, G: Git, PR: Prompter> RepoCloner<'_, MC, G, PR> {
+    /// Clones the repo from its remote if it is missing, returning whether it was cloned
+    pub fn clone_if_missing(&self, repo: &Repo, yes: bool) -> Result<bool> {
+        let Some(remote) = &repo.remote else {
+            debug!("No remote for {}, nothing to clone", repo.path.display());
+            return Ok(false);
+        };
+
+        match self.exists.check(repo) {
+            Err(DirectoryCheckError::DoesNotExist) => (),
+            _ => return Ok(false),
+        }
+
+        info!("Repo {} is missing", repo.path.display());
+        if yes
+            || self.prompter.confirm(
+                format!("Clone {} into {}?", remote.as_str(), repo.path.display()),
+                false,
+            )?
+        {
+            self.git.clone_repo(remote.as_str(), &repo.path)?;
+            Ok(true)
+        } else {
+            Err(RepoClonerError::DeclinedToClone)
+        }
+    }
+}
@@ -1,11 +1,19 @@
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
+use clap_complete::ArgValueCompleter;
 use clap_verbosity_flag::{Verbosity, WarnLevel};
 
+use super::{completion::complete_repo_path, exit::EXIT_CODES_HELP, logging::LogFormat};
+use crate::{
+    action::link_selector::{ClobberPolicy, LinkPolicy, WrongLinkPolicy},
+    config::{file::Strictness, schema::SchemaKind},
+    util::{prompting::InputMode, shell::Shell},
+};
+
 /// Yvan Vivid's tool to manage his (or your) home environment  
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_long_help = EXIT_CODES_HELP)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
@@ -30,6 +38,35 @@ pub struct Cli {
     #[arg(long)]
     pub dry_run: bool,
 
+    /// Answer yes to every confirmation without asking. Files in the way of links are still
+    /// left alone unless --on-clobber says otherwise
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Never prompt, taking the default answer to every question
+    #[arg(long, conflicts_with = "yes")]
+    pub no_input: bool,
+
+    /// What to do with a file in the way of a link, instead of asking
+    #[arg(long, value_enum)]
+    pub on_clobber: Option<ClobberPolicy>,
+
+    /// What to do with a link pointing somewhere else, instead of asking
+    #[arg(long, value_enum)]
+    pub on_wrong_link: Option<WrongLinkPolicy>,
+
+    /// Wait for another running dotzo to finish instead of failing
+    #[arg(long)]
+    pub wait: bool,
+
+    /// Warn about unknown keys in .dot files instead of failing
+    #[arg(long)]
+    pub lenient: bool,
+
+    /// Format of the log records, on the console and in the log file
+    #[arg(long, value_enum, default_value_t)]
+    pub log_format: LogFormat,
+
     /// Level of verbosity - defaults to warn, -v for info, -vv for debug
     #[command(flatten)]
     pub verbose: Verbosity<WarnLevel>,
@@ -42,10 +79,133 @@ pub enum Command {
 
     // Setup,
     /// Sync dotfiles from repo to home environment
-    Sync,
+    Sync {
+        /// Only sync mappings for these repo or target paths
+        #[arg(add = ArgValueCompleter::new(complete_repo_path))]
+        paths: Vec<PathBuf>,
+    },
+
+    /// Sync again whenever the repos change, until interrupted
+    Watch {
+        /// Milliseconds without changes to wait for before syncing
+        #[arg(long, default_value_t = 500)]
+        debounce: u64,
+    },
+
+    /// Pull the latest changes into the repos, then sync
+    Pull,
+
+    /// Commit and push changes to the repos
+    Push {
+        /// Commit message
+        #[arg(short, long, default_value = "Update dotfiles")]
+        message: String,
+    },
 
     /// Show info about home environment
     Info,
+
+    /// Show the state of each link managed by the repos
+    Status {
+        /// Exit with the drift code if any link is not in place
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Store a file from home in the repo as an encrypted secret
+    Adopt {
+        /// File directly in home or the config directory
+        path: PathBuf,
+
+        /// Directory under etc in the repo to put it in, named after the file by default
+        #[arg(long)]
+        into: Option<PathBuf>,
+
+        /// Store it encrypted, leaving the file in place as its decrypted copy
+        #[arg(long, required = true)]
+        encrypt: bool,
+    },
+
+    /// Move the repo, updating the rc file and every link into it
+    Relocate {
+        /// Where to move the repo to, inside home
+        path: PathBuf,
+    },
+
+    /// Remove links left dangling by sources removed from the repos
+    Clean,
+
+    /// Check the environment for problems
+    Doctor {
+        /// Apply the fixes that are safe to make
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Print shell exports for the inferred environment
+    Env {
+        /// Shell to format for, guessed from $SHELL by default
+        #[arg(long, value_enum)]
+        shell: Option<Shell>,
+    },
+
+    /// Print a shell completion script
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+
+        /// Complete through dotzo itself, which also completes paths in the repos
+        #[arg(long)]
+        dynamic: bool,
+    },
+
+    /// Validate every .dot spec in the repo
+    CheckSpec,
+
+    /// Print the JSON Schema for .dot or .dotrc files
+    Schema {
+        #[arg(value_enum, default_value_t)]
+        kind: SchemaKind,
+    },
+}
+
+impl Command {
+    /// Whether the command can change the environment, so must not run alongside another
+    pub fn changes_files(&self) -> bool {
+        !matches!(
+            self,
+            Command::Status { .. }
+                | Command::Env { .. }
+                | Command::CheckSpec
+                | Command::Schema { .. }
+                | Command::Completions { .. }
+                | Command::Doctor { fix: false }
+        )
+    }
+}
+
+impl Cli {
+    pub fn input_mode(&self) -> InputMode {
+        if self.yes {
+            InputMode::AssumeYes
+        } else if self.no_input {
+            InputMode::Defaults
+        } else {
+            InputMode::Interactive
+        }
+    }
+
+    pub fn link_policy(&self) -> LinkPolicy {
+        LinkPolicy::new(self.on_clobber, self.on_wrong_link, false)
+    }
+
+    pub fn spec_strictness(&self) -> Strictness {
+        if self.lenient {
+            Strictness::Lenient
+        } else {
+            Strictness::Strict
+        }
+    }
 }
 
 pub fn parse_cli() -> Cli {
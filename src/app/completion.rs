@@ -0,0 +1,150 @@
+use std::{
+    collections::BTreeSet,
+    ffi::{OsStr, OsString},
+    path::PathBuf,
+};
+
+use clap::CommandFactory;
+use clap_complete::CompletionCandidate;
+
+use super::cli::Cli;
+use crate::{
+    components::{
+        environment::inference::{DirsEnvironmentInference, EnvironmentInference},
+        repo::types::Repo,
+    },
+    util::fs::{DirectoryListing, MetadataChecks, StandardFsRead},
+};
+
+/// Overrides given earlier on the command line being completed
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Overrides {
+    home_dir: Option<PathBuf>,
+    config_dir: Option<PathBuf>,
+    repo: Option<PathBuf>,
+    config: Option<PathBuf>,
+}
+
+impl Overrides {
+    // The words are incomplete, so parsing errors are ignored and whatever was parsed is kept
+    fn from_words(words: impl IntoIterator<Item = OsString>) -> Self {
+        let Ok(matches) = Cli::command()
+            .ignore_errors(true)
+            .try_get_matches_from(words)
+        else {
+            return Self::default();
+        };
+        let path = |id: &str| matches.get_one::<PathBuf>(id).cloned();
+        Self {
+            home_dir: path("home_dir"),
+            config_dir: path("config_dir"),
+            repo: path("repo"),
+            config: path("config"),
+        }
+    }
+}
+
+/// Completes paths relative to the root of each configured repo, for arguments naming a dotfile
+pub fn complete_repo_path(current: &OsStr) -> Vec<CompletionCandidate> {
+    // The shell calls back with the words being completed after a `--`
+    let overrides =
+        Overrides::from_words(std::env::args_os().skip_while(|arg| arg != "--").skip(1));
+
+    let inference = DirsEnvironmentInference::new();
+    let repos = inference
+        .create_home(overrides.home_dir)
+        .and_then(|home| {
+            let rc = inference.load_rc(&home, overrides.config)?;
+            let environment = inference.create(home, &rc, overrides.config_dir)?;
+            Ok(Repo::all_from_config(&environment, &rc, overrides.repo))
+        })
+        .unwrap_or_default();
+
+    repo_path_candidates(&StandardFsRead::new(), &repos, &current.to_string_lossy())
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+fn repo_path_candidates<FS: MetadataChecks + DirectoryListing>(
+    fs: &FS,
+    repos: &[Repo],
+    current: &str,
+) -> BTreeSet<String> {
+    let (dir, prefix) = match current.rfind('/') {
+        Some(i) => current.split_at(i + 1),
+        None => ("", current),
+    };
+
+    let mut candidates = BTreeSet::new();
+    for repo in repos {
+        let Ok(entries) = fs.read_dir(repo.path.join(dir)) else {
+            continue;
+        };
+        for path in entries.flatten() {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let hidden = name.starts_with('.') && !prefix.starts_with('.');
+            if hidden || !name.starts_with(prefix) {
+                continue;
+            }
+            let suffix = if fs.is_dir(&path) { "/" } else { "" };
+            candidates.insert(format!("{}{}{}", dir, name, suffix));
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::fs::testing::{TestFile, TestFs};
+
+    #[test]
+    fn test_overrides_from_partial_words() {
+        let words = [
+            "dotzo",
+            "--home-dir",
+            "/tmp/home",
+            "--repo=/tmp/dots",
+            "sync",
+            "etc/v",
+        ];
+        assert_eq!(
+            Overrides::from_words(words.map(OsString::from)),
+            Overrides {
+                home_dir: Some(PathBuf::from("/tmp/home")),
+                repo: Some(PathBuf::from("/tmp/dots")),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            Overrides::from_words(["dotzo", "sync"].map(OsString::from)),
+            Overrides::default()
+        );
+    }
+
+    #[test]
+    fn test_repo_path_candidates() {
+        let fs = TestFs::new([
+            (PathBuf::from("/home/_/.git"), TestFile::Directory),
+            (PathBuf::from("/home/_/etc/vimrc"), TestFile::Regular),
+            (PathBuf::from("/home/_/etc/tmux"), TestFile::Directory),
+            (PathBuf::from("/home/team/etc/vim"), TestFile::Directory),
+        ]);
+        let repos = [
+            Repo::new(PathBuf::from("/home/_"), None),
+            Repo::new(PathBuf::from("/home/team"), None),
+        ];
+
+        assert_eq!(
+            repo_path_candidates(&fs, &repos, ""),
+            BTreeSet::from(["etc/".to_string()])
+        );
+        assert_eq!(
+            repo_path_candidates(&fs, &repos, "etc/v"),
+            BTreeSet::from(["etc/vim/".to_string(), "etc/vimrc".to_string()])
+        );
+    }
+}
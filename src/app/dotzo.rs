@@ -5,6 +5,7 @@ use crate::{
     util::{
         actions::Actions,
         fs::{DirectoryListing, FsRead, LinkReader, MetadataChecks},
+        git::Git,
         prompting::Prompter,
     },
 };
@@ -20,6 +21,7 @@ pub struct DotzoApp<
     A: Actions,
     PR: Prompter,
     EI: EnvironmentInference,
+    G: Git,
 > {
     metadata_checks: &'a MC,
     link_reader: &'a LR,
@@ -27,6 +29,7 @@ pub struct DotzoApp<
     actions: &'a A,
     prompter: &'a PR,
     inference: &'a EI,
+    git: &'a G,
 }
 
 impl<
@@ -37,7 +40,8 @@ impl<
         A: Actions,
         PR: Prompter,
         EI: EnvironmentInference,
-    > App<'a> for DotzoApp<'a, MC, LR, DL, A, PR, EI>
+        G: Git,
+    > App<'a> for DotzoApp<'a, MC, LR, DL, A, PR, EI, G>
 {
     type MC = MC;
     type LR = LR;
@@ -45,6 +49,7 @@ impl<
     type A = A;
     type PR = PR;
     type EI = EI;
+    type G = G;
 
     fn metadata_checks(&self) -> &'a Self::MC {
         self.metadata_checks
@@ -69,10 +74,22 @@ impl<
     fn inference(&self) -> &'a Self::EI {
         self.inference
     }
+
+    fn git(&self) -> &'a Self::G {
+        self.git
+    }
 }
 
-impl<'a, FS: FsRead, A: Actions, PR: Prompter, EI: EnvironmentInference> DotzoApp<'a, FS, FS, FS, A, PR, EI> {
-    pub fn new_with_fs(fs: &'a FS, actions: &'a A, prompter: &'a PR, inference: &'a EI) -> Self {
-        Self::new(fs, fs, fs, actions, prompter, inference)
+impl<'a, FS: FsRead, A: Actions, PR: Prompter, EI: EnvironmentInference, G: Git>
+    DotzoApp<'a, FS, FS, FS, A, PR, EI, G>
+{
+    pub fn new_with_fs(
+        fs: &'a FS,
+        actions: &'a A,
+        prompter: &'a PR,
+        inference: &'a EI,
+        git: &'a G,
+    ) -> Self {
+        Self::new(fs, fs, fs, actions, prompter, inference, git)
     }
 }
@@ -0,0 +1,199 @@
+use std::{error::Error, process::ExitCode};
+
+use crate::{
+    action::{
+        directory_creator::DirectoryCreatorError, link_selector::LinkSelectorError,
+        repo_cloner::RepoClonerError,
+    },
+    components::{
+        environment::{
+            checks::{
+                home::HomeCheckError, structure::StructureCheckError, tree::LayoutCheckError,
+            },
+            inference::EnvironmentInferenceError,
+        },
+        repo::checks::structure::StructureCheckError as RepoStructureCheckError,
+    },
+    config::{file::ConfigFileReadError, spec::translate::SpecContextError},
+    tasks::{
+        adopt::AdoptTaskError, check_spec::CheckSpecTaskError, clean::CleanTaskError,
+        doctor::DoctorTaskError, init::InitTaskError, push::PushTaskError,
+        relocate::RelocateTaskError, status::StatusTaskError, sync::SyncTaskError,
+    },
+    util::{prompting::PrompterError, secret::VaultError},
+};
+
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  Success
+  1  Any other failure, such as an IO or git error
+  2  Invalid arguments
+  3  A .dotrc or .dot file is missing or invalid
+  4  The home environment or a repo is missing or badly structured
+  5  Links differ from what the repos describe
+  6  A confirmation was declined, or could not be asked
+  7  Some links or hooks failed while others were applied";
+
+/// Classes of failure, each exiting with its own documented code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    General = 1,
+    // Shared with clap's own usage errors
+    Usage = 2,
+    Config = 3,
+    Environment = 4,
+    Drift = 5,
+    Declined = 6,
+    Partial = 7,
+}
+
+impl Failure {
+    // The class of a single error, if it decides one
+    fn of(error: &(dyn Error + 'static)) -> Option<Self> {
+        if error.is::<ConfigFileReadError>()
+            || error.is::<SpecContextError>()
+            || error.is::<VaultError>()
+        {
+            return Some(Failure::Config);
+        }
+        if error.is::<HomeCheckError>()
+            || error.is::<LayoutCheckError>()
+            || error.is::<StructureCheckError>()
+            || error.is::<RepoStructureCheckError>()
+        {
+            return Some(Failure::Environment);
+        }
+
+        if let Some(e) = error.downcast_ref::<EnvironmentInferenceError>() {
+            return Some(match e {
+                EnvironmentInferenceError::CannotInferHome => Failure::Environment,
+                _ => Failure::Config,
+            });
+        }
+        if let Some(InitTaskError::NoRepo) = error.downcast_ref() {
+            return Some(Failure::Config);
+        }
+        if let Some(CheckSpecTaskError::Problems(_)) = error.downcast_ref() {
+            return Some(Failure::Config);
+        }
+        if let Some(e) = error.downcast_ref::<SyncTaskError>() {
+            match e {
+                SyncTaskError::DirtyRepo(_) => return Some(Failure::Environment),
+                SyncTaskError::NoMatch(_) | SyncTaskError::TooBroad(_) => {
+                    return Some(Failure::Usage)
+                }
+                SyncTaskError::PartialFailure { .. } => return Some(Failure::Partial),
+                _ => (),
+            }
+        }
+        if let Some(PushTaskError::Conflicts(_)) = error.downcast_ref() {
+            return Some(Failure::Environment);
+        }
+        if let Some(DoctorTaskError::Problems(_)) = error.downcast_ref() {
+            return Some(Failure::Environment);
+        }
+        if let Some(StatusTaskError::Drift(_)) = error.downcast_ref() {
+            return Some(Failure::Drift);
+        }
+        if let Some(LinkSelectorError::Clobber(_) | LinkSelectorError::WrongLink(_)) =
+            error.downcast_ref()
+        {
+            return Some(Failure::Drift);
+        }
+
+        if let Some(DirectoryCreatorError::DeclinedToCreate) = error.downcast_ref() {
+            return Some(Failure::Declined);
+        }
+        if let Some(RepoClonerError::DeclinedToClone) = error.downcast_ref() {
+            return Some(Failure::Declined);
+        }
+        if let Some(CleanTaskError::Declined) = error.downcast_ref() {
+            return Some(Failure::Declined);
+        }
+        if let Some(e) = error.downcast_ref::<RelocateTaskError>() {
+            match e {
+                RelocateTaskError::NoRepo | RelocateTaskError::RcNotFound => {
+                    return Some(Failure::Config)
+                }
+                RelocateTaskError::OutsideHome(_) | RelocateTaskError::Exists(_) => {
+                    return Some(Failure::Usage)
+                }
+                RelocateTaskError::Declined => return Some(Failure::Declined),
+                RelocateTaskError::PartialFailure { .. } => return Some(Failure::Partial),
+                _ => (),
+            }
+        }
+        if let Some(e) = error.downcast_ref::<AdoptTaskError>() {
+            match e {
+                AdoptTaskError::NoRepo | AdoptTaskError::NoSecretKey => {
+                    return Some(Failure::Config)
+                }
+                AdoptTaskError::NotAFile(_)
+                | AdoptTaskError::NotInDestination(_)
+                | AdoptTaskError::Exists(_) => return Some(Failure::Usage),
+                AdoptTaskError::Declined => return Some(Failure::Declined),
+                _ => (),
+            }
+        }
+        if let Some(PrompterError::NotInteractive(_) | PrompterError::Cancelled) =
+            error.downcast_ref()
+        {
+            return Some(Failure::Declined);
+        }
+        None
+    }
+
+    /// The class decided by the innermost error in the chain that decides one
+    pub fn classify<'e>(chain: impl IntoIterator<Item = &'e (dyn Error + 'static)>) -> Self {
+        chain
+            .into_iter()
+            .filter_map(Self::of)
+            .last()
+            .unwrap_or(Failure::General)
+    }
+}
+
+impl From<Failure> for ExitCode {
+    fn from(failure: Failure) -> Self {
+        ExitCode::from(failure as u8)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{components::repo::tree::TreeTraverserError, tasks::run::RunTaskError};
+
+    fn classify(error: RunTaskError) -> Failure {
+        Failure::classify(anyhow::Error::from(error).chain())
+    }
+
+    #[test]
+    fn test_classify() {
+        let spec = SpecContextError::DuplicateSource {
+            source_name: "vimrc".into(),
+            first: crate::mapping::Destination::Home,
+            second: crate::mapping::Destination::Config,
+        };
+        assert_eq!(
+            classify(SyncTaskError::Traversal(TreeTraverserError::Spec(spec)).into()),
+            Failure::Config
+        );
+        assert_eq!(
+            classify(InitTaskError::Clone(RepoClonerError::DeclinedToClone).into()),
+            Failure::Declined
+        );
+        assert_eq!(
+            classify(
+                SyncTaskError::Selection(LinkSelectorError::Prompt(PrompterError::Cancelled))
+                    .into()
+            ),
+            Failure::Declined
+        );
+        assert_eq!(classify(StatusTaskError::Drift(2).into()), Failure::Drift);
+        assert_eq!(
+            classify(SyncTaskError::Io(std::io::Error::other("disk")).into()),
+            Failure::General
+        );
+    }
+}
@@ -1,27 +1,288 @@
 use chrono::Local;
+use clap::ValueEnum;
 use colored::Colorize;
-use fern::Dispatch;
-use log::{Level, LevelFilter, SetLoggerError};
-use std::io::stderr;
+use fern::{Dispatch, FormatCallback, Output};
+use log::{
+    kv::{self, Key, VisitSource},
+    Level, LevelFilter, Record, SetLoggerError,
+};
+use serde_json::{Map, Value};
+use std::{
+    fmt::Arguments,
+    fs::{create_dir_all, rename, File, OpenOptions},
+    io::{stderr, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
 
-pub fn setup_logging(level: LevelFilter) -> Result<(), SetLoggerError> {
-    Dispatch::new()
-        .format(|out, message, record| {
-            let color = match record.level() {
-                Level::Error => "red",
-                Level::Warn => "yellow",
-                Level::Info => "green",
-                Level::Debug => "blue",
-                Level::Trace => "magenta",
-            };
-            out.finish(format_args!(
-                "{} [{}] {}",
-                Local::now().format("%Y-%m-%d %H:%M:%S"),
-                record.level().to_string().color(color),
-                message
-            ))
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Lines to read, colored on the console
+    #[default]
+    Text,
+    /// One JSON object per line, to collect and query
+    Json,
+}
+
+// The key values given with a record, such as paths and link statuses
+struct Fields(Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(flag) = value.to_bool() {
+            Value::from(flag)
+        } else if let Some(number) = value.to_u64() {
+            Value::from(number)
+        } else if let Some(number) = value.to_i64() {
+            Value::from(number)
+        } else {
+            Value::from(value.to_string())
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+fn json_record(message: &Arguments, record: &Record) -> String {
+    let mut fields = Fields(Map::new());
+    // Visiting the fields can't fail
+    let _ = record.key_values().visit(&mut fields);
+
+    let mut object = Map::new();
+    object.insert("timestamp".into(), Local::now().to_rfc3339().into());
+    object.insert("level".into(), record.level().as_str().into());
+    object.insert(
+        "module".into(),
+        record.module_path().unwrap_or(record.target()).into(),
+    );
+    object.insert("message".into(), message.to_string().into());
+    if !fields.0.is_empty() {
+        object.insert("fields".into(), Value::Object(fields.0));
+    }
+    Value::Object(object).to_string()
+}
+
+fn console_record(out: FormatCallback, message: &Arguments, record: &Record) {
+    let color = match record.level() {
+        Level::Error => "red",
+        Level::Warn => "yellow",
+        Level::Info => "green",
+        Level::Debug => "blue",
+        Level::Trace => "magenta",
+    };
+    out.finish(format_args!(
+        "{} [{}] {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        record.level().to_string().color(color),
+        message
+    ))
+}
+
+fn file_record(out: FormatCallback, message: &Arguments, record: &Record) {
+    out.finish(format_args!(
+        "{} [{}] {}: {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        record.level(),
+        record.target(),
+        message
+    ))
+}
+
+// The log file is moved aside once it reaches this size, keeping this many old ones
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+const KEPT_LOGS: usize = 3;
+
+enum Sink {
+    // Records from before the state directory is known
+    Buffered(Vec<u8>),
+    Open { dir: PathBuf, file: File, size: u64 },
+    Off,
+}
+
+fn open_log(dir: &Path) -> std::io::Result<(File, u64)> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join("dotzo.log"))?;
+    let size = file.metadata()?.len();
+    Ok((file, size))
+}
+
+// Shifts dotzo.log to dotzo.log.1 and so on, the oldest being overwritten
+fn rotate(dir: &Path) -> std::io::Result<()> {
+    let name = |n: usize| match n {
+        0 => dir.join("dotzo.log"),
+        n => dir.join(format!("dotzo.log.{}", n)),
+    };
+    for n in (0..KEPT_LOGS).rev() {
+        match rename(name(n), name(n + 1)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+impl Sink {
+    fn write(&mut self, record: &[u8]) -> std::io::Result<()> {
+        match self {
+            Sink::Buffered(buffer) => buffer.extend_from_slice(record),
+            Sink::Open { dir, file, size } => {
+                if *size >= MAX_LOG_SIZE {
+                    rotate(dir)?;
+                    (*file, *size) = open_log(dir)?;
+                }
+                file.write_all(record)?;
+                *size += record.len() as u64;
+            }
+            Sink::Off => {}
+        }
+        Ok(())
+    }
+}
+
+/// The log file in the state directory, holding records until the directory is known
+#[derive(Clone)]
+pub struct LogFile(Arc<Mutex<Sink>>);
+
+impl LogFile {
+    fn new() -> Self {
+        Self(Arc::new(Mutex::new(Sink::Buffered(Vec::new()))))
+    }
+
+    /// Starts writing to dotzo.log in the directory, beginning with the records held so far
+    pub fn open(&self, dir: &Path) -> std::io::Result<()> {
+        let mut sink = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let Sink::Buffered(buffered) = std::mem::replace(&mut *sink, Sink::Off) else {
+            return Ok(());
+        };
+        create_dir_all(dir)?;
+        let (file, size) = open_log(dir)?;
+        *sink = Sink::Open {
+            dir: dir.to_owned(),
+            file,
+            size,
+        };
+        sink.write(&buffered)
+    }
+
+    /// Drops the records held so far and any after, when there is nowhere to put the log file
+    pub fn close(&self) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Sink::Off;
+    }
+}
+
+impl Write for LogFile {
+    fn write(&mut self, record: &[u8]) -> std::io::Result<usize> {
+        let mut sink = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        // A log file that can't be written is given up on, rather than failing every record
+        if sink.write(record).is_err() {
+            *sink = Sink::Off;
+        }
+        Ok(record.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut *self.0.lock().unwrap_or_else(PoisonError::into_inner) {
+            Sink::Open { file, .. } => file.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Logs to stderr at the given level, and everything from dotzo at debug level to the log file
+pub fn setup_logging(level: LevelFilter, format: LogFormat) -> Result<LogFile, SetLoggerError> {
+    let log_file = LogFile::new();
+    let console = Dispatch::new()
+        .format(move |out, message, record| match format {
+            LogFormat::Text => console_record(out, message, record),
+            LogFormat::Json => out.finish(format_args!("{}", json_record(message, record))),
         })
         .level(level)
-        .chain(stderr())
+        .chain(stderr());
+    let file = Dispatch::new()
+        .format(move |out, message, record| match format {
+            LogFormat::Text => file_record(out, message, record),
+            LogFormat::Json => out.finish(format_args!("{}", json_record(message, record))),
+        })
+        .level(LevelFilter::Debug)
+        .filter(|metadata| metadata.target().starts_with("dotzo"))
+        .chain(Output::writer(Box::new(log_file.clone()), "\n"));
+
+    Dispatch::new()
+        .chain(console)
+        .chain(file)
         .apply()
+        .map(|_| log_file)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{read_to_string, write};
+
+    use super::*;
+    use crate::util::fs::testing::ScratchDir;
+
+    #[test]
+    fn test_json_record() {
+        let fields = [("path", "/home/.vimrc"), ("status", "confirmed")];
+        let record = Record::builder()
+            .level(Level::Info)
+            .target("dotzo::action::make_link")
+            .module_path(Some("dotzo::action::make_link"))
+            .key_values(&fields)
+            .build();
+
+        let json: Value =
+            serde_json::from_str(&json_record(&format_args!("Linked"), &record)).unwrap();
+        assert_eq!(json["level"], "INFO");
+        assert_eq!(json["module"], "dotzo::action::make_link");
+        assert_eq!(json["message"], "Linked");
+        assert_eq!(json["fields"]["path"], "/home/.vimrc");
+        assert_eq!(json["fields"]["status"], "confirmed");
+        assert!(json["timestamp"].is_string());
+    }
+
+    #[test]
+    fn test_buffers_then_rotates() {
+        let root = ScratchDir::new("logs-rotate");
+        let dir = root.join("logs");
+        let mut log_file = LogFile::new();
+        log_file.write_all(b"before\n").unwrap();
+        log_file.open(&dir).unwrap();
+        log_file.write_all(b"after\n").unwrap();
+        assert_eq!(
+            read_to_string(dir.join("dotzo.log")).unwrap(),
+            "before\nafter\n"
+        );
+
+        write(dir.join("dotzo.log"), vec![b'x'; MAX_LOG_SIZE as usize]).unwrap();
+        write(dir.join(format!("dotzo.log.{}", KEPT_LOGS - 1)), "older").unwrap();
+        write(dir.join(format!("dotzo.log.{}", KEPT_LOGS)), "oldest").unwrap();
+        let mut log_file = LogFile::new();
+        log_file.open(&dir).unwrap();
+        log_file.write_all(b"rotated\n").unwrap();
+        assert_eq!(read_to_string(dir.join("dotzo.log")).unwrap(), "rotated\n");
+        assert_eq!(
+            std::fs::metadata(dir.join("dotzo.log.1")).unwrap().len(),
+            MAX_LOG_SIZE
+        );
+        assert_eq!(
+            read_to_string(dir.join(format!("dotzo.log.{}", KEPT_LOGS))).unwrap(),
+            "older"
+        );
+        assert!(!dir.join(format!("dotzo.log.{}", KEPT_LOGS + 1)).exists());
+    }
+
+    #[test]
+    fn test_closed_stays_closed() {
+        let root = ScratchDir::new("logs-closed");
+        let dir = root.join("logs");
+        let mut log_file = LogFile::new();
+        log_file.write_all(b"before\n").unwrap();
+        log_file.close();
+        log_file.write_all(b"after\n").unwrap();
+        log_file.open(&dir).unwrap();
+        assert!(!dir.exists());
+    }
 }
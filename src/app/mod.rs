@@ -1,4 +1,6 @@
 pub mod cli;
+pub mod completion;
 pub mod dotzo;
+pub mod exit;
 pub mod logging;
 pub mod types;
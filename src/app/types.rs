@@ -1,5 +1,5 @@
 use crate::{
-    action::directory_creator::DirectoryCreator,
+    action::{directory_creator::DirectoryCreator, repo_cloner::RepoCloner},
     components::{
         environment::{
             checks::{home::HomeCheck, structure::StructureCheck, tree::LayoutCheck},
@@ -7,9 +7,11 @@ use crate::{
         },
         repo::checks::structure::StructureCheck as RepoStructureCheck,
     },
+    mapping::Permissions,
     util::{
         actions::Actions,
         fs::{DirectoryListing, LinkReader, MetadataChecks},
+        git::Git,
         prompting::Prompter,
     },
     validation::{containment::ContainmentCheck, directory::DirectoryCheck},
@@ -22,6 +24,7 @@ pub trait App<'a> {
     type A: Actions;
     type PR: Prompter;
     type EI: EnvironmentInference;
+    type G: Git;
 
     fn metadata_checks(&self) -> &'a Self::MC;
     fn link_reader(&self) -> &'a Self::LR;
@@ -29,14 +32,16 @@ pub trait App<'a> {
     fn actions(&self) -> &'a Self::A;
     fn prompter(&self) -> &'a Self::PR;
     fn inference(&self) -> &'a Self::EI;
+    fn git(&self) -> &'a Self::G;
 
     fn layout_check(
         &self,
         yes: bool,
         create_directories: bool,
+        permissions: Option<Permissions>,
     ) -> LayoutCheck<'a, Self::MC, Self::A, Self::PR> {
         let directory_checker = DirectoryCheck::new(self.metadata_checks());
-        let directory_creator = DirectoryCreator::new(self.actions(), self.prompter());
+        let directory_creator = DirectoryCreator::new(self.actions(), self.prompter(), permissions);
         LayoutCheck::new(
             directory_checker,
             directory_creator,
@@ -59,6 +64,14 @@ pub trait App<'a> {
         )
     }
 
+    fn repo_cloner(&self) -> RepoCloner<'a, Self::MC, Self::G, Self::PR> {
+        RepoCloner::new(
+            DirectoryCheck::new(self.metadata_checks()),
+            self.git(),
+            self.prompter(),
+        )
+    }
+
     fn home_check(&self) -> HomeCheck<'a, Self::MC> {
         HomeCheck::new(DirectoryCheck::new(self.metadata_checks()))
     }
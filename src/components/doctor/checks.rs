@@ -0,0 +1,420 @@
+use std::path::{Component, Path, PathBuf};
+
+use derive_more::derive::Constructor;
+
+use super::types::{DoctorCheck, Finding, Remedy, Severity};
+use crate::{
+    components::{
+        dotzo::types::Dotzo,
+        environment::checks::{home::HomeCheck, structure::StructureCheck, tree::LayoutCheck},
+        linker::dangling::{DanglingLink, DanglingScan},
+        repo::{checks::structure::StructureCheck as RepoStructureCheck, types::Repo},
+    },
+    util::{
+        actions::Actions,
+        dir::LabeledDir,
+        error::reason,
+        fs::{DirectoryListing, LinkReader, MetadataChecks},
+        prompting::Prompter,
+    },
+};
+
+/// The checks every other command runs first, reported instead of failing
+#[derive(Debug, Constructor)]
+pub struct CoreCheck<'a, MC: MetadataChecks, LR: LinkReader, A: Actions, PR: Prompter> {
+    home_check: HomeCheck<'a, MC>,
+    // Must not create directories, so it never prompts
+    layout_check: LayoutCheck<'a, MC, A, PR>,
+    structure_check: StructureCheck<'a, MC, LR>,
+    repo_structure_check: RepoStructureCheck<'a, MC>,
+}
+
+impl<MC: MetadataChecks, LR: LinkReader, A: Actions, PR: Prompter> DoctorCheck
+    for CoreCheck<'_, MC, LR, A, PR>
+{
+    fn name(&self) -> &'static str {
+        "core"
+    }
+
+    fn run(&self, dotzo: &Dotzo) -> Vec<Finding> {
+        let error = |e: &(dyn std::error::Error + 'static), remedy: Option<&str>| {
+            Finding::new(
+                Severity::Error,
+                reason(e),
+                remedy.map(|r| Remedy::Suggest(r.to_owned())),
+            )
+        };
+
+        let mut findings = Vec::new();
+        if let Err(e) = self.home_check.check(&dotzo.environment.home) {
+            findings.push(error(&e, None));
+        }
+        if let Err(e) = self.layout_check.check(&dotzo.environment) {
+            findings.push(error(&e, Some("run `dotzo init` to create it")));
+        }
+        if let Err(e) = self.structure_check.check(&dotzo.environment) {
+            findings.push(error(&e, None));
+        }
+        for repo in &dotzo.repos {
+            if let Err(e) = self.repo_structure_check.check(repo) {
+                findings.push(error(
+                    &e,
+                    Some("check the repo location in .dotrc, or run `dotzo init` to clone it"),
+                ));
+            }
+        }
+        findings
+    }
+}
+
+// Where a link into a moved repo would point now, matched by its path under etc in each repo
+fn moved_sources(linked: &Path, repos: &[Repo]) -> Vec<PathBuf> {
+    let components: Vec<Component> = linked.components().collect();
+    let Some(etc) = components.iter().rposition(|c| c.as_os_str() == "etc") else {
+        return Vec::new();
+    };
+    let rest: PathBuf = components[etc + 1..].iter().collect();
+    repos.iter().map(|repo| repo.etc().join(&rest)).collect()
+}
+
+/// Broken symlinks in the destination directories that point into a repo, or into where a
+/// repo used to be
+#[derive(Debug, Constructor)]
+pub struct LinkCheck<'a, MC: MetadataChecks, LR: LinkReader, DL: DirectoryListing> {
+    metadata_checks: &'a MC,
+    link_reader: &'a LR,
+    directory_listing: &'a DL,
+}
+
+impl<MC: MetadataChecks, LR: LinkReader, DL: DirectoryListing> LinkCheck<'_, MC, LR, DL> {
+    fn check_link(&self, dangling: DanglingLink, repos: &[Repo]) -> Option<Finding> {
+        let DanglingLink { path, linked, repo } = dangling;
+        if let Some(repo) = repo {
+            return Some(Finding::new(
+                Severity::Warning,
+                format!(
+                    "{} points to {}, which is no longer in {}",
+                    path.display(),
+                    linked.display(),
+                    repo.display()
+                ),
+                Some(Remedy::RemoveLink(path)),
+            ));
+        }
+
+        let source = moved_sources(&linked, repos)
+            .into_iter()
+            .find(|source| self.metadata_checks.exists(source))?;
+        Some(Finding::new(
+            Severity::Warning,
+            format!(
+                "{} points to {}, an old location of {}",
+                path.display(),
+                linked.display(),
+                source.display()
+            ),
+            Some(Remedy::Suggest(
+                "run `dotzo sync --on-wrong-link fix` to relink it".to_owned(),
+            )),
+        ))
+    }
+}
+
+impl<MC: MetadataChecks, LR: LinkReader, DL: DirectoryListing> DoctorCheck
+    for LinkCheck<'_, MC, LR, DL>
+{
+    fn name(&self) -> &'static str {
+        "links"
+    }
+
+    fn run(&self, dotzo: &Dotzo) -> Vec<Finding> {
+        DanglingScan::new(
+            self.metadata_checks,
+            self.link_reader,
+            self.directory_listing,
+            dotzo.rc.scan_depth,
+        )
+        .scan(&dotzo.environment, &dotzo.repos)
+        .into_iter()
+        .filter_map(|dangling| self.check_link(dangling, &dotzo.repos))
+        .collect()
+    }
+}
+
+/// ~/.ssh and the keys in it must not be open to other users, or ssh refuses them
+#[derive(Debug, Constructor)]
+pub struct SshCheck<'a, MC: MetadataChecks, DL: DirectoryListing> {
+    metadata_checks: &'a MC,
+    directory_listing: &'a DL,
+}
+
+// What ssh refuses in the mode of a path
+#[derive(Debug, Clone, Copy)]
+struct SshMode {
+    refused: u32,
+    // What the refused bits let other users do
+    allows: &'static str,
+}
+
+// The directory and config may be readable by others, but not writable
+const SSH_SHARED: SshMode = SshMode {
+    refused: 0o022,
+    allows: "write to",
+};
+
+const SSH_PRIVATE_KEY: SshMode = SshMode {
+    refused: 0o077,
+    allows: "access",
+};
+
+impl<MC: MetadataChecks, DL: DirectoryListing> SshCheck<'_, MC, DL> {
+    fn check_mode(&self, path: &Path, expected: SshMode) -> Option<Finding> {
+        let mode = self.metadata_checks.mode(path).ok()?;
+        (mode & expected.refused != 0).then(|| {
+            Finding::new(
+                Severity::Error,
+                format!(
+                    "{} has mode {:o}, which other users can {}",
+                    path.display(),
+                    mode,
+                    expected.allows
+                ),
+                Some(Remedy::Suggest(format!(
+                    "chmod {:o} {}",
+                    mode & !expected.refused,
+                    path.display()
+                ))),
+            )
+        })
+    }
+}
+
+fn ssh_file_mode(path: &Path) -> Option<SshMode> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    if name == "config" {
+        Some(SSH_SHARED)
+    } else if name.starts_with("id_") && !name.ends_with(".pub") {
+        Some(SSH_PRIVATE_KEY)
+    } else {
+        None
+    }
+}
+
+impl<MC: MetadataChecks, DL: DirectoryListing> DoctorCheck for SshCheck<'_, MC, DL> {
+    fn name(&self) -> &'static str {
+        "ssh"
+    }
+
+    fn run(&self, dotzo: &Dotzo) -> Vec<Finding> {
+        let ssh = dotzo.environment.home.as_ref().join(".ssh");
+        if !self.metadata_checks.is_dir(&ssh) {
+            return Vec::new();
+        }
+
+        let mut findings: Vec<Finding> = self.check_mode(&ssh, SSH_SHARED).into_iter().collect();
+        if let Ok(entries) = self.directory_listing.read_dir(&ssh) {
+            let mut files: Vec<(PathBuf, SshMode)> = entries
+                .flatten()
+                .filter_map(|path| Some((path.clone(), ssh_file_mode(&path)?)))
+                .collect();
+            files.sort_by(|(a, _), (b, _)| a.cmp(b));
+            findings.extend(
+                files
+                    .iter()
+                    .filter_map(|(file, expected)| self.check_mode(file, *expected)),
+            );
+        }
+        findings
+    }
+}
+
+/// Repos on another filesystem than home can't be moved into place or hard linked
+#[derive(Debug, Constructor)]
+pub struct FilesystemCheck<'a, MC: MetadataChecks> {
+    metadata_checks: &'a MC,
+}
+
+impl<MC: MetadataChecks> DoctorCheck for FilesystemCheck<'_, MC> {
+    fn name(&self) -> &'static str {
+        "filesystem"
+    }
+
+    fn run(&self, dotzo: &Dotzo) -> Vec<Finding> {
+        let Ok(home) = self.metadata_checks.device(&dotzo.environment.home) else {
+            return Vec::new();
+        };
+        dotzo
+            .repos
+            .iter()
+            .filter(|repo| {
+                self.metadata_checks
+                    .device(&repo.path)
+                    .is_ok_and(|device| device != home)
+            })
+            .map(|repo| {
+                Finding::new(
+                    Severity::Info,
+                    format!(
+                        "Repo {} is on a different filesystem from home",
+                        repo.path.display()
+                    ),
+                    Some(Remedy::Suggest(
+                        "move the repo onto the same filesystem as home".to_owned(),
+                    )),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The directories dotzo and other programs write into must be writable
+#[derive(Debug, Constructor)]
+pub struct WritableCheck<'a, MC: MetadataChecks> {
+    metadata_checks: &'a MC,
+}
+
+impl<MC: MetadataChecks> WritableCheck<'_, MC> {
+    fn check<D: LabeledDir>(&self, dir: &D) -> Option<Finding> {
+        let writable = !self.metadata_checks.exists(dir) || self.metadata_checks.is_writable(dir);
+        (!writable).then(|| {
+            Finding::new(
+                Severity::Error,
+                format!("The {} directory {} is not writable", D::LABEL, dir),
+                Some(Remedy::Suggest(format!("chmod u+w {}", dir))),
+            )
+        })
+    }
+}
+
+impl<MC: MetadataChecks> DoctorCheck for WritableCheck<'_, MC> {
+    fn name(&self) -> &'static str {
+        "writable"
+    }
+
+    fn run(&self, dotzo: &Dotzo) -> Vec<Finding> {
+        let environment = &dotzo.environment;
+        [
+            self.check(&environment.config),
+            self.check(&environment.data),
+            self.check(&environment.state),
+            self.check(&environment.cache),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        components::environment::types::Environment,
+        config::rc::types::Rc,
+        util::fs::testing::{TestFile, TestFs},
+    };
+
+    use super::*;
+
+    fn dotzo() -> Dotzo {
+        Dotzo::new(
+            Environment::new(
+                PathBuf::from("/home").into(),
+                PathBuf::from("/home/.config").into(),
+                PathBuf::from("/home/.local/share").into(),
+                PathBuf::from("/home/.local/state").into(),
+                PathBuf::from("/home/.cache").into(),
+            ),
+            vec![Repo::new(PathBuf::from("/home/_"), None)],
+            Rc::default(),
+        )
+    }
+
+    fn test_fs() -> TestFs {
+        let mut fs = TestFs::new([
+            (PathBuf::from("/home/_/etc/vimrc"), TestFile::Regular),
+            (
+                PathBuf::from("/home/.vimrc"),
+                TestFile::Symlink(PathBuf::from("_/etc/vimrc")),
+            ),
+            (
+                PathBuf::from("/home/.bashrc"),
+                TestFile::Symlink(PathBuf::from("_/etc/bashrc")),
+            ),
+            (
+                PathBuf::from("/home/.config/nvim"),
+                TestFile::Symlink(PathBuf::from("../old/etc/vimrc")),
+            ),
+            (PathBuf::from("/home/.ssh/id_ed25519"), TestFile::Regular),
+            (
+                PathBuf::from("/home/.ssh/id_ed25519.pub"),
+                TestFile::Regular,
+            ),
+        ]);
+        fs.add_directory("/home/.ssh");
+        fs.add_directory("/home/.local/state");
+        fs.modes.insert(PathBuf::from("/home/.ssh"), 0o700);
+        fs.modes.insert(PathBuf::from("/home/.local/state"), 0o555);
+        fs
+    }
+
+    #[test]
+    fn test_link_check() {
+        let fs = test_fs();
+        let findings = LinkCheck::new(&fs, &fs, &fs).run(&dotzo());
+        assert_eq!(findings.len(), 2);
+        assert!(findings.contains(&Finding::new(
+            Severity::Warning,
+            "/home/.bashrc points to /home/_/etc/bashrc, which is no longer in /home/_".into(),
+            Some(Remedy::RemoveLink(PathBuf::from("/home/.bashrc"))),
+        )));
+        assert!(findings
+            .iter()
+            .any(|f| f.message.ends_with("an old location of /home/_/etc/vimrc")));
+    }
+
+    #[test]
+    fn test_ssh_check() {
+        let fs = test_fs();
+        let findings = SshCheck::new(&fs, &fs).run(&dotzo());
+        assert_eq!(
+            findings,
+            vec![Finding::new(
+                Severity::Error,
+                "/home/.ssh/id_ed25519 has mode 644, which other users can access".into(),
+                Some(Remedy::Suggest("chmod 600 /home/.ssh/id_ed25519".into())),
+            )]
+        );
+    }
+
+    #[test]
+    fn test_ssh_check_readable_config() {
+        let mut fs = test_fs();
+        fs.add_file(PathBuf::from("/home/.ssh/config"), TestFile::Regular);
+        fs.modes.insert(PathBuf::from("/home/.ssh"), 0o755);
+        fs.modes.insert(PathBuf::from("/home/.ssh/config"), 0o644);
+        fs.modes
+            .insert(PathBuf::from("/home/.ssh/id_ed25519"), 0o600);
+        assert_eq!(SshCheck::new(&fs, &fs).run(&dotzo()), vec![]);
+
+        fs.modes.insert(PathBuf::from("/home/.ssh/config"), 0o664);
+        assert_eq!(
+            SshCheck::new(&fs, &fs).run(&dotzo()),
+            vec![Finding::new(
+                Severity::Error,
+                "/home/.ssh/config has mode 664, which other users can write to".into(),
+                Some(Remedy::Suggest("chmod 644 /home/.ssh/config".into())),
+            )]
+        );
+    }
+
+    #[test]
+    fn test_writable_check() {
+        let fs = test_fs();
+        let findings = WritableCheck::new(&fs).run(&dotzo());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "The state directory /home/.local/state is not writable"
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use derive_more::derive::{Constructor, Display};
+
+use crate::components::dotzo::types::Dotzo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display)]
+pub enum Severity {
+    #[display("info")]
+    Info,
+
+    #[display("warning")]
+    Warning,
+
+    #[display("error")]
+    Error,
+}
+
+/// How to fix a finding
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+pub enum Remedy {
+    // Left to the user
+    #[display("{_0}")]
+    Suggest(String),
+
+    // Safe to apply with --fix, since only a symlink is removed
+    #[display("remove the link {}", _0.display())]
+    RemoveLink(PathBuf),
+}
+
+impl Remedy {
+    pub fn is_automatic(&self) -> bool {
+        !matches!(self, Remedy::Suggest(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Constructor)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+    pub remedy: Option<Remedy>,
+}
+
+/// One health check of the environment, reporting problems rather than failing on them
+pub trait DoctorCheck {
+    fn name(&self) -> &'static str;
+    fn run(&self, dotzo: &Dotzo) -> Vec<Finding>;
+}
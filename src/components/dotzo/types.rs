@@ -1,9 +1,26 @@
 use derive_more::derive::Constructor;
 
-use crate::components::{environment::types::Environment, repo::types::Repo};
+use crate::{
+    components::{environment::types::Environment, repo::types::Repo},
+    config::rc::types::Rc,
+    util::secret::{self, Vault},
+};
 
 #[derive(Debug, Constructor)]
 pub struct Dotzo {
     pub environment: Environment,
-    pub repo: Repo,
+    // In priority order
+    pub repos: Vec<Repo>,
+    pub rc: Rc,
+}
+
+impl Dotzo {
+    /// The vault for the secret key in the rc file, if one is set
+    pub fn vault(&self) -> secret::Result<Option<Vault>> {
+        self.rc
+            .secret_key
+            .as_ref()
+            .map(|key| Vault::load(&key.to_path(self.environment.home.as_ref())))
+            .transpose()
+    }
 }
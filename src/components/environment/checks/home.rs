@@ -1,5 +1,4 @@
 use derive_more::derive::Constructor;
-use log::error;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -14,6 +13,7 @@ pub enum HomeCheckError {
     #[error("Home check failed at {path} with {error:?}")]
     Check {
         path: PathBuf,
+        #[source]
         error: DirectoryCheckError,
     },
 }
@@ -1,5 +1,4 @@
 use derive_more::derive::Constructor;
-use log::error;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -18,6 +17,7 @@ pub enum StructureCheckError {
     Containment {
         label: &'static str,
         path: PathBuf,
+        #[source]
         error: ContainmentError,
     },
 }
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use derive_more::derive::Constructor;
-use log::{debug, error};
+use log::debug;
 use thiserror::Error;
 
 use crate::{
@@ -17,6 +17,7 @@ pub enum LayoutCheckError {
     Check {
         label: &'static str,
         path: PathBuf,
+        #[source]
         error: DirectoryCheckError,
     },
 
@@ -24,6 +25,7 @@ pub enum LayoutCheckError {
     Creation {
         label: &'static str,
         path: PathBuf,
+        #[source]
         error: DirectoryCreatorError,
     },
 }
@@ -66,7 +66,12 @@ pub trait EnvironmentInference:
     + CoreInference<StateDir>
     + CoreInference<CacheDir>
 {
-    fn load_rc(&self, home: &Home) -> Result<Rc> {
+    /// Reads the rc file given, or else the one found in home
+    fn load_rc(&self, home: &Home, given: Option<PathBuf>) -> Result<Rc> {
+        if let Some(path) = given {
+            info!("Reading the given rc file: {}", path.display());
+            return Rc::read_from_path(path)?.ok_or(EnvironmentInferenceError::RcNotFound);
+        }
         debug!("Looking for a config in home: {}", home.as_ref().display());
         Rc::find_in_path(home)?.ok_or(EnvironmentInferenceError::RcNotFound)
     }
@@ -2,7 +2,11 @@ use derive_more::derive::{AsRef, Constructor, Display, From};
 use relative_path::RelativePathBuf;
 use std::path::{Path, PathBuf};
 
-use crate::{dir, label, labeled_dir, mapping::Destination, util::dir::Labeled};
+use crate::{
+    dir, label, labeled_dir,
+    mapping::{Destination, LocatedTarget},
+    util::dir::Labeled,
+};
 
 labeled_dir!(Home, "home");
 
@@ -52,4 +56,9 @@ impl Environment {
             Destination::Config => DestinationData::new(false, self.config.as_ref()),
         }
     }
+
+    pub fn target_path(&self, located: &LocatedTarget) -> PathBuf {
+        let data = self.destination_data(&located.destination);
+        data.path.join(located.target.resolve(data.dot_default))
+    }
 }
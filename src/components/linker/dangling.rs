@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+
+use derive_more::derive::Constructor;
+
+use crate::{
+    components::{environment::types::Environment, repo::types::Repo},
+    util::fs::{normalize, DirectoryListing, LinkReader, MetadataChecks},
+};
+
+/// A symlink whose destination does not exist
+#[derive(Debug, Clone, PartialEq, Eq, Constructor)]
+pub struct DanglingLink {
+    pub path: PathBuf,
+    // Where the link points, resolved against its directory
+    pub linked: PathBuf,
+    // The repo the link points into, if any
+    pub repo: Option<PathBuf>,
+}
+
+/// Looks for dangling links in the destination directories, down to a bounded depth
+#[derive(Debug, Constructor)]
+pub struct DanglingScan<'a, MC: MetadataChecks, LR: LinkReader, DL: DirectoryListing> {
+    metadata_checks: &'a MC,
+    link_reader: &'a LR,
+    directory_listing: &'a DL,
+    // Directories below each destination to look in, 1 for just the destination itself
+    depth: usize,
+}
+
+impl<MC: MetadataChecks, LR: LinkReader, DL: DirectoryListing> DanglingScan<'_, MC, LR, DL> {
+    fn dangling(&self, path: PathBuf, repos: &[Repo]) -> Option<DanglingLink> {
+        let linked = self.link_reader.read_link(&path).ok()?;
+        let linked = normalize(&path.parent()?.join(linked));
+        if self.metadata_checks.exists(&linked) {
+            return None;
+        }
+        let repo = repos
+            .iter()
+            .find(|repo| linked.starts_with(&repo.path))
+            .map(|repo| repo.path.clone());
+        Some(DanglingLink::new(path, linked, repo))
+    }
+
+    fn scan_dir(
+        &self,
+        dir: &Path,
+        depth: usize,
+        skip: &[&Path],
+        repos: &[Repo],
+        found: &mut Vec<DanglingLink>,
+    ) {
+        let Ok(entries) = self.directory_listing.read_dir(dir) else {
+            return;
+        };
+        let mut entries: Vec<PathBuf> = entries.flatten().collect();
+        entries.sort();
+        for path in entries {
+            if self.metadata_checks.is_symlink(&path) {
+                found.extend(self.dangling(path, repos));
+            } else if depth > 1
+                && self.metadata_checks.is_real_dir(&path)
+                && !skip.contains(&path.as_path())
+            {
+                self.scan_dir(&path, depth - 1, skip, repos, found);
+            }
+        }
+    }
+
+    /// Every dangling link under home and the config dir, not looking inside the repos
+    pub fn scan(&self, environment: &Environment, repos: &[Repo]) -> Vec<DanglingLink> {
+        let roots = [environment.home.as_ref(), environment.config.as_ref()];
+        // The config dir is scanned on its own, so the home scan skips it
+        let skip: Vec<&Path> = roots
+            .iter()
+            .copied()
+            .chain(repos.iter().map(|repo| repo.path.as_path()))
+            .collect();
+
+        let mut found = Vec::new();
+        for root in roots {
+            self.scan_dir(root, self.depth, &skip, repos, &mut found);
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::fs::testing::{TestFile, TestFs};
+
+    fn environment() -> Environment {
+        Environment::new(
+            PathBuf::from("/home").into(),
+            PathBuf::from("/home/.config").into(),
+            PathBuf::from("/home/.local/share").into(),
+            PathBuf::from("/home/.local/state").into(),
+            PathBuf::from("/home/.cache").into(),
+        )
+    }
+
+    fn link(path: &str, linked: &str) -> (PathBuf, TestFile) {
+        (
+            PathBuf::from(path),
+            TestFile::Symlink(PathBuf::from(linked)),
+        )
+    }
+
+    #[test]
+    fn test_scan_to_depth() {
+        let fs = TestFs::new([
+            (PathBuf::from("/home/_/etc/vimrc"), TestFile::Regular),
+            link("/home/.vimrc", "_/etc/vimrc"),
+            link("/home/.bashrc", "_/etc/bashrc"),
+            link("/home/.config/nvim", "../_/etc/nvim"),
+            link("/home/.config/a/b/deep", "/home/_/etc/deep"),
+            link("/home/.elsewhere", "/opt/nothing"),
+            link("/home/_/etc/broken", "missing"),
+        ]);
+        let repos = [Repo::new(PathBuf::from("/home/_"), None)];
+
+        let found = DanglingScan::new(&fs, &fs, &fs, 2).scan(&environment(), &repos);
+        assert_eq!(
+            found,
+            vec![
+                DanglingLink::new(
+                    PathBuf::from("/home/.bashrc"),
+                    PathBuf::from("/home/_/etc/bashrc"),
+                    Some(PathBuf::from("/home/_"))
+                ),
+                DanglingLink::new(
+                    PathBuf::from("/home/.elsewhere"),
+                    PathBuf::from("/opt/nothing"),
+                    None
+                ),
+                DanglingLink::new(
+                    PathBuf::from("/home/.config/nvim"),
+                    PathBuf::from("/home/_/etc/nvim"),
+                    Some(PathBuf::from("/home/_"))
+                ),
+            ]
+        );
+
+        let deeper = DanglingScan::new(&fs, &fs, &fs, 3).scan(&environment(), &repos);
+        assert_eq!(deeper.len(), 4);
+    }
+}
@@ -1,11 +1,18 @@
 use derive_more::derive::Constructor;
 use relative_path::{FromPathError, PathExt, RelativePathBuf, RelativeToError};
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 
 use crate::{
     components::environment::types::Environment,
-    mapping::DotMap,
-    util::fs::{LinkReader, MetadataChecks},
+    mapping::{DotMap, LinkMode, LinkStyle, Permissions},
+    util::{
+        fs::{normalize, LinkReader, MetadataChecks},
+        secret::{digest, Vault, VaultError},
+    },
 };
 
 use super::types::{DotLink, DotStatus};
@@ -18,6 +25,12 @@ pub enum DotLinkerError {
     #[error("Relative path error: {0}")]
     RelativePath(#[from] RelativeToError),
 
+    #[error("{} is a secret, but no secret_key is set in the rc file", .0.display())]
+    NoSecretKey(PathBuf),
+
+    #[error("Secret error: {0}")]
+    Vault(#[from] VaultError),
+
     #[error("Io error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -28,39 +41,342 @@ pub type Result<T> = core::result::Result<T, DotLinkerError>;
 pub struct DotLinker<'a, MC: MetadataChecks, LR: LinkReader> {
     metadata_checks: &'a MC,
     link_reader: &'a LR,
+    // Style for mappings that don't set their own
+    style: LinkStyle,
+    // Only needed for secret mappings
+    vault: Option<&'a Vault>,
 }
 
 impl<MC: MetadataChecks, LR: LinkReader> DotLinker<'_, MC, LR> {
+    // Canonicalizes as much of the path as exists, so links can be worked out for sources
+    // that are not in place yet
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        match self.link_reader.canonicalize(path) {
+            Err(e) if e.kind() == ErrorKind::NotFound => match (path.parent(), path.file_name()) {
+                (Some(parent), Some(name)) => Ok(self.canonicalize(parent)?.join(name)),
+                _ => Err(e.into()),
+            },
+            result => Ok(result?),
+        }
+    }
+
+    fn style(&self, map: &DotMap) -> LinkStyle {
+        map.target.target.link.unwrap_or(self.style)
+    }
+
     pub fn create_link(&self, environment: &Environment, map: &DotMap) -> Result<DotLink> {
-        let source_path = self.link_reader.canonicalize(&map.source)?;
-        let data = environment.destination_data(&map.target.destination);
-        let target_directory = data.path;
-        let target_path = target_directory.join(map.target.target.resolve(data.dot_default));
-        let link_path = source_path.relative_to(target_directory)?;
-        Ok(DotLink::new(target_path, link_path))
+        let source_path = self.canonicalize(&map.source)?;
+        let target_path = environment.target_path(&map.target);
+        let mode = map.target.target.mode;
+        let link_path = match (mode, self.style(map)) {
+            (LinkMode::Hardlink | LinkMode::Secret, _) | (_, LinkStyle::Absolute) => source_path,
+            (LinkMode::Symlink, LinkStyle::Relative) => {
+                let target_directory = environment.destination_data(&map.target.destination).path;
+                source_path.relative_to(target_directory)?.to_path("")
+            }
+        };
+        Ok(DotLink::new(
+            map.source.clone(),
+            target_path,
+            link_path,
+            mode,
+            match mode {
+                // Decrypted copies are private unless declared otherwise
+                LinkMode::Secret => map.target.target.permissions.or(Some(Permissions(0o600))),
+                _ => map.target.target.permissions,
+            },
+        ))
+    }
+
+    /// Decrypted contents of a secret's source
+    pub fn decrypt(&self, link: &DotLink) -> Result<Vec<u8>> {
+        let vault = self
+            .vault
+            .ok_or_else(|| DotLinkerError::NoSecretKey(link.source.clone()))?;
+        Ok(vault.decrypt(&self.link_reader.read_file(&link.link)?)?)
     }
 
+    // Confirmed when the target is a copy of the decrypted source
+    fn check_secret(&self, link: &DotLink, is_symlink: bool) -> Result<DotStatus> {
+        if is_symlink {
+            return Ok(DotStatus::Symlink(
+                self.link_reader.read_link(&link.target)?,
+            ));
+        }
+        if !self.metadata_checks.is_file(&link.target) {
+            return Ok(DotStatus::Clobber);
+        }
+        let installed = self.link_reader.read_file(&link.target)?;
+        Ok(if digest(&installed) == digest(&self.decrypt(link)?) {
+            DotStatus::Confirmed
+        } else {
+            DotStatus::ChangedSecret
+        })
+    }
+
+    // Confirmed when the target is the same file as the source, on the same device. A separate
+    // file with the same contents can be relinked without losing anything
+    fn check_hard_link(&self, link: &DotLink, is_symlink: bool) -> Result<DotStatus> {
+        if is_symlink {
+            return Ok(DotStatus::Symlink(
+                self.link_reader.read_link(&link.target)?,
+            ));
+        }
+        let same_file = self.metadata_checks.device(&link.target)?
+            == self.metadata_checks.device(&link.link)?
+            && self.metadata_checks.inode(&link.target)?
+                == self.metadata_checks.inode(&link.link)?;
+        if same_file {
+            return Ok(DotStatus::Confirmed);
+        }
+        let unchanged = self.metadata_checks.is_file(&link.target)
+            && self.link_reader.read_file(&link.target)?
+                == self.link_reader.read_file(&link.link)?;
+        Ok(if unchanged {
+            DotStatus::StaleHardLink
+        } else {
+            DotStatus::Clobber
+        })
+    }
+
+    /// Status of the link, where a link in place only counts once its permissions are right
     pub fn check(&self, link: &DotLink) -> Result<DotStatus> {
+        let status = self.check_link(link)?;
+        match link.permissions {
+            Some(permissions) if status == DotStatus::Confirmed => {
+                let mode = self.metadata_checks.mode(&link.target)?;
+                Ok(if mode == permissions.0 {
+                    DotStatus::Confirmed
+                } else {
+                    DotStatus::WrongPermissions(Permissions(mode))
+                })
+            }
+            _ => Ok(status),
+        }
+    }
+
+    fn check_link(&self, link: &DotLink) -> Result<DotStatus> {
         if !self.metadata_checks.exists(&link.target) {
             return Ok(DotStatus::Pending);
         }
 
-        if !self.metadata_checks.is_symlink(&link.target) {
+        let is_symlink = self.metadata_checks.is_symlink(&link.target);
+        match link.mode {
+            LinkMode::Hardlink => return self.check_hard_link(link, is_symlink),
+            LinkMode::Secret => return self.check_secret(link, is_symlink),
+            LinkMode::Symlink => (),
+        }
+        if !is_symlink {
             return Ok(DotStatus::Clobber);
         }
 
         let linked = self.link_reader.read_link(&link.target)?;
 
-        if linked.is_absolute() {
-            // TODO: handle wrong absolute link case
-            return Ok(DotStatus::AbsoluteLink(linked));
+        // The link must be in the configured style as well as point at the source
+        match (linked.is_absolute(), link.link.is_absolute()) {
+            (true, true) if normalize(&linked) == normalize(&link.link) => Ok(DotStatus::Confirmed),
+            (true, true) => Ok(DotStatus::WrongAbsoluteLink(linked)),
+            (true, false) => Ok(DotStatus::AbsoluteLink(linked)),
+            (false, true) => Ok(DotStatus::RelativeLink(RelativePathBuf::from_path(
+                &linked,
+            )?)),
+            (false, false) => {
+                let rel_linked = RelativePathBuf::from_path(&linked)?.normalize();
+                if rel_linked != RelativePathBuf::from_path(&link.link)?.normalize() {
+                    return Ok(DotStatus::WrongLink(rel_linked));
+                }
+                Ok(DotStatus::Confirmed)
+            }
         }
+    }
+}
 
-        let rel_linked = RelativePathBuf::from_path(&linked)?.normalize();
-        if rel_linked != link.link {
-            return Ok(DotStatus::WrongLink(rel_linked));
-        }
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        mapping::{Destination, Target},
+        util::fs::testing::{TestFile, TestFs},
+    };
+    use age::x25519::Identity;
+
+    fn environment() -> Environment {
+        Environment::new(
+            PathBuf::from("/home").into(),
+            PathBuf::from("/home/.config").into(),
+            PathBuf::from("/home/.local/share").into(),
+            PathBuf::from("/home/.local/state").into(),
+            PathBuf::from("/home/.cache").into(),
+        )
+    }
+
+    fn dot_map(name: &str, link: Option<LinkStyle>, mode: LinkMode) -> DotMap {
+        DotMap::new(
+            PathBuf::from("/home/_/etc").join(name),
+            Destination::Home.locate(Target::new(name.into(), None, link, mode, None)),
+        )
+    }
+
+    #[test]
+    fn test_link_style() {
+        let fs = TestFs::new([
+            (PathBuf::from("/home/_/etc/vimrc"), TestFile::Regular),
+            (PathBuf::from("/home/_/etc/bashrc"), TestFile::Regular),
+            (
+                PathBuf::from("/home/.vimrc"),
+                TestFile::Symlink(PathBuf::from("_/etc/vimrc")),
+            ),
+            (
+                PathBuf::from("/home/.bashrc"),
+                TestFile::Symlink(PathBuf::from("/home/_/etc/bashrc")),
+            ),
+        ]);
+        let linker = DotLinker::new(&fs, &fs, LinkStyle::Absolute, None);
+        let environment = environment();
+
+        let vimrc = linker
+            .create_link(&environment, &dot_map("vimrc", None, LinkMode::Symlink))
+            .unwrap();
+        assert_eq!(vimrc.link, PathBuf::from("/home/_/etc/vimrc"));
+        assert_eq!(
+            linker.check(&vimrc).unwrap(),
+            DotStatus::RelativeLink("_/etc/vimrc".into())
+        );
+
+        let bashrc = linker
+            .create_link(&environment, &dot_map("bashrc", None, LinkMode::Symlink))
+            .unwrap();
+        assert_eq!(linker.check(&bashrc).unwrap(), DotStatus::Confirmed);
+
+        // A mapping's own style wins over the linker's
+        let vimrc = linker
+            .create_link(
+                &environment,
+                &dot_map("vimrc", Some(LinkStyle::Relative), LinkMode::Symlink),
+            )
+            .unwrap();
+        assert_eq!(vimrc.link, PathBuf::from("_/etc/vimrc"));
+        assert_eq!(linker.check(&vimrc).unwrap(), DotStatus::Confirmed);
+    }
+
+    #[test]
+    fn test_hard_link() {
+        let mut fs = TestFs::new([
+            (PathBuf::from("/home/_/etc/vimrc"), TestFile::Regular),
+            (PathBuf::from("/home/_/etc/bashrc"), TestFile::Regular),
+            (
+                PathBuf::from("/home/.vimrc"),
+                TestFile::Symlink(PathBuf::from("_/etc/vimrc")),
+            ),
+            (PathBuf::from("/home/.bashrc"), TestFile::Regular),
+            (PathBuf::from("/home/_/etc/inputrc"), TestFile::Regular),
+            (PathBuf::from("/home/.inputrc"), TestFile::Regular),
+            (PathBuf::from("/home/_/etc/gitconfig"), TestFile::Regular),
+            (PathBuf::from("/home/_/etc/profile"), TestFile::Regular),
+            (PathBuf::from("/home/.profile"), TestFile::Regular),
+        ]);
+        let inode = fs.inode("/home/_/etc/bashrc").unwrap();
+        fs.inodes.insert(PathBuf::from("/home/.bashrc"), inode);
+        fs.contents.insert(
+            PathBuf::from("/home/_/etc/inputrc"),
+            b"set bell-style".to_vec(),
+        );
+        fs.contents.insert(
+            PathBuf::from("/home/.inputrc"),
+            b"set editing-mode".to_vec(),
+        );
+        fs.contents
+            .insert(PathBuf::from("/home/_/etc/profile"), b"umask 022".to_vec());
+        fs.contents
+            .insert(PathBuf::from("/home/.profile"), b"umask 022".to_vec());
+        let linker = DotLinker::new(&fs, &fs, LinkStyle::Relative, None);
+        let environment = environment();
+        let check = |name: &str| {
+            let link = linker
+                .create_link(&environment, &dot_map(name, None, LinkMode::Hardlink))
+                .unwrap();
+            assert_eq!(link.link, PathBuf::from("/home/_/etc").join(name));
+            linker.check(&link).unwrap()
+        };
+
+        assert_eq!(check("bashrc"), DotStatus::Confirmed);
+        assert_eq!(check("inputrc"), DotStatus::Clobber);
+        assert_eq!(check("profile"), DotStatus::StaleHardLink);
+        assert_eq!(check("gitconfig"), DotStatus::Pending);
+        assert_eq!(
+            check("vimrc"),
+            DotStatus::Symlink(PathBuf::from("_/etc/vimrc"))
+        );
+    }
+
+    #[test]
+    fn test_permissions() {
+        let mut fs = TestFs::new([
+            (PathBuf::from("/home/_/etc/ssh"), TestFile::Regular),
+            (
+                PathBuf::from("/home/.ssh"),
+                TestFile::Symlink(PathBuf::from("/home/_/etc/ssh")),
+            ),
+        ]);
+        let linker = DotLinker::new(&fs, &fs, LinkStyle::Absolute, None);
+        let map = DotMap::new(
+            PathBuf::from("/home/_/etc/ssh"),
+            Destination::Home.locate(Target::new(
+                "ssh".into(),
+                None,
+                None,
+                LinkMode::Symlink,
+                Some(Permissions(0o700)),
+            )),
+        );
+        let link = linker.create_link(&environment(), &map).unwrap();
+        assert_eq!(
+            linker.check(&link).unwrap(),
+            DotStatus::WrongPermissions(Permissions(0o644))
+        );
+
+        fs.modes.insert(PathBuf::from("/home/_/etc/ssh"), 0o700);
+        let linker = DotLinker::new(&fs, &fs, LinkStyle::Absolute, None);
+        assert_eq!(linker.check(&link).unwrap(), DotStatus::Confirmed);
+    }
+
+    #[test]
+    fn test_secret() {
+        let vault = Vault::new(Identity::generate());
+        let mut fs = TestFs::new([
+            (PathBuf::from("/home/_/etc/netrc"), TestFile::Regular),
+            (PathBuf::from("/home/.netrc"), TestFile::Regular),
+        ]);
+        fs.contents.insert(
+            PathBuf::from("/home/_/etc/netrc"),
+            vault.encrypt(b"machine").unwrap().into_bytes(),
+        );
+        fs.contents
+            .insert(PathBuf::from("/home/.netrc"), b"machine".to_vec());
+        let map = dot_map("netrc", None, LinkMode::Secret);
+
+        let linker = DotLinker::new(&fs, &fs, LinkStyle::Relative, Some(&vault));
+        let link = linker.create_link(&environment(), &map).unwrap();
+        assert_eq!(link.link, PathBuf::from("/home/_/etc/netrc"));
+        assert_eq!(link.permissions, Some(Permissions(0o600)));
+        assert_eq!(
+            linker.check(&link).unwrap(),
+            DotStatus::WrongPermissions(Permissions(0o644))
+        );
+
+        fs.modes.insert(PathBuf::from("/home/.netrc"), 0o600);
+        let linker = DotLinker::new(&fs, &fs, LinkStyle::Relative, Some(&vault));
+        assert_eq!(linker.check(&link).unwrap(), DotStatus::Confirmed);
+
+        fs.contents
+            .insert(PathBuf::from("/home/.netrc"), b"edited".to_vec());
+        let linker = DotLinker::new(&fs, &fs, LinkStyle::Relative, Some(&vault));
+        assert_eq!(linker.check(&link).unwrap(), DotStatus::ChangedSecret);
 
-        Ok(DotStatus::Confirmed)
+        let linker = DotLinker::new(&fs, &fs, LinkStyle::Relative, None);
+        assert!(matches!(
+            linker.check(&link),
+            Err(DotLinkerError::NoSecretKey(_))
+        ));
     }
 }
@@ -1,3 +1,5 @@
+pub mod dangling;
 pub mod link;
 pub mod reconciliation;
+pub mod report;
 pub mod types;
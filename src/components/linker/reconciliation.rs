@@ -1,3 +1,4 @@
+use log::debug;
 use thiserror::Error;
 
 use crate::{
@@ -25,6 +26,9 @@ pub struct DotReconciliation {
     pub pending: DotLinkSet,
     pub clobber: DotLinkSet,
     pub fix: DotLinkSet,
+    pub relink: DotLinkSet,
+    pub overwrite: DotLinkSet,
+    pub permissions: DotLinkSet,
 }
 
 impl DotReconciliation {
@@ -36,13 +40,25 @@ impl DotReconciliation {
         let mut recon = DotReconciliation::default();
         for dot_map in dot_maps {
             let link = linker.create_link(environment, &dot_map)?;
-            match linker.check(&link)? {
+            let status = linker.check(&link)?;
+            debug!(
+                path:% = link.target.display(), status:% = status;
+                "{} is {}",
+                link.target.display(),
+                status
+            );
+            match status {
                 DotStatus::Confirmed => recon.confirmed.insert(link),
                 DotStatus::Pending => recon.pending.insert(link),
                 DotStatus::Clobber => recon.clobber.insert(link),
+                DotStatus::ChangedSecret => recon.overwrite.insert(link),
                 DotStatus::WrongLink(_relative_path_buf) => recon.fix.insert(link),
                 DotStatus::AbsoluteLink(_path_buf) => recon.fix.insert(link),
+                DotStatus::RelativeLink(_relative_path_buf) => recon.fix.insert(link),
                 DotStatus::WrongAbsoluteLink(_path_buf) => recon.fix.insert(link),
+                DotStatus::Symlink(_path_buf) => recon.fix.insert(link),
+                DotStatus::StaleHardLink => recon.relink.insert(link),
+                DotStatus::WrongPermissions(_permissions) => recon.permissions.insert(link),
             };
         }
         Ok(recon)
@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+
+use derive_more::derive::Display;
+
+use crate::action::make_link::LinkChange;
+
+/// What happened to one link during a sync
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+pub enum LinkOutcome {
+    #[display("{_0}")]
+    Applied(LinkChange),
+
+    // The link turned out to be in place already
+    #[display("unchanged")]
+    Unchanged,
+
+    #[display("skipped {_0}")]
+    Skipped(LinkChange),
+
+    #[display("failed to {_0}: {_1}")]
+    Failed(LinkChange, String),
+}
+
+impl LinkOutcome {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, LinkOutcome::Failed(..))
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            LinkOutcome::Applied(_) => "done",
+            LinkOutcome::Unchanged => "unchanged",
+            LinkOutcome::Skipped(_) => "skipped",
+            LinkOutcome::Failed(..) => "failed",
+        }
+    }
+}
+
+/// Outcomes of a sync, kept in the order they happened
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub outcomes: Vec<(PathBuf, LinkOutcome)>,
+
+    // Hooks that failed, apart from the links since the links they ran for are in place
+    pub hook_failures: Vec<String>,
+}
+
+impl SyncReport {
+    pub fn record(&mut self, path: impl Into<PathBuf>, outcome: LinkOutcome) {
+        self.outcomes.push((path.into(), outcome));
+    }
+
+    pub fn record_hook_failure(&mut self, cause: String) {
+        self.hook_failures.push(cause);
+    }
+
+    /// Link operations that failed
+    pub fn failures(&self) -> usize {
+        self.count(LinkOutcome::is_failure)
+    }
+
+    /// Whether any link operation or hook failed
+    pub fn has_failures(&self) -> bool {
+        self.failures() > 0 || !self.hook_failures.is_empty()
+    }
+
+    fn count(&self, filter: impl Fn(&LinkOutcome) -> bool) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|(_, outcome)| filter(outcome))
+            .count()
+    }
+
+    /// One line per outcome and failed hook, then the totals
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .outcomes
+            .iter()
+            .map(|(path, outcome)| {
+                format!("{:<10} {} ({})", outcome.label(), path.display(), outcome)
+            })
+            .chain(
+                self.hook_failures
+                    .iter()
+                    .map(|cause| format!("{:<10} {}", "hook", cause)),
+            )
+            .collect();
+        let mut totals = format!(
+            "{} done, {} unchanged, {} skipped, {} failed",
+            self.count(|outcome| matches!(outcome, LinkOutcome::Applied(_))),
+            self.count(|outcome| matches!(outcome, LinkOutcome::Unchanged)),
+            self.count(|outcome| matches!(outcome, LinkOutcome::Skipped(_))),
+            self.failures()
+        );
+        if !self.hook_failures.is_empty() {
+            totals.push_str(&format!(", {} hooks failed", self.hook_failures.len()));
+        }
+        lines.push(totals);
+        lines
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::{actions::Error as ActionError, error::reason};
+
+    #[test]
+    fn test_report_lines() {
+        let mut report = SyncReport::default();
+        report.record("/home/.vimrc", LinkOutcome::Applied(LinkChange::Create));
+        report.record("/home/.bashrc", LinkOutcome::Skipped(LinkChange::Backup));
+        let error = ActionError::from(std::io::Error::other("read-only"));
+        report.record(
+            "/home/.tmux.conf",
+            LinkOutcome::Failed(LinkChange::Fix, reason(&error)),
+        );
+
+        assert_eq!(report.failures(), 1);
+        assert_eq!(
+            report.lines(),
+            vec![
+                "done       /home/.vimrc (create)",
+                "skipped    /home/.bashrc (skipped back up and replace)",
+                "failed     /home/.tmux.conf (failed to fix link: IO error: read-only)",
+                "1 done, 0 unchanged, 1 skipped, 1 failed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hook_failures_apart_from_links() {
+        let mut report = SyncReport::default();
+        report.record("/home/.tmux.conf", LinkOutcome::Applied(LinkChange::Create));
+        report.record_hook_failure("on_link hook in /home/_/etc/tmux failed".to_string());
+
+        assert_eq!(report.failures(), 0);
+        assert!(report.has_failures());
+        assert_eq!(
+            report.lines(),
+            vec![
+                "done       /home/.tmux.conf (create)",
+                "hook       on_link hook in /home/_/etc/tmux failed",
+                "1 done, 0 unchanged, 0 skipped, 0 failed, 1 hooks failed",
+            ]
+        );
+    }
+}
@@ -1,35 +1,73 @@
-use derive_more::derive::Constructor;
+use derive_more::derive::{Constructor, Display};
 use relative_path::RelativePathBuf;
 use std::{collections::HashSet, path::PathBuf};
 
-#[derive(Debug, PartialEq, Eq)]
+use crate::mapping::{LinkMode, Permissions};
+
+#[derive(Debug, PartialEq, Eq, Display)]
 pub enum DotStatus {
     // DotMap already correct
+    #[display("confirmed")]
     Confirmed,
 
     // DotMap can be created without issue
+    #[display("pending")]
     Pending,
 
     // DotMap target is already there and not a link
+    #[display("clobber")]
     Clobber,
 
     // DotMap target is already there but points to a different source
+    #[display("wrong link to {_0}")]
     WrongLink(RelativePathBuf),
 
     // DotMap target points to the right file but with an absolute link
+    #[display("absolute link to {}", _0.display())]
     AbsoluteLink(PathBuf),
 
+    // DotMap target points to the right file but with a relative link, when absolute is wanted
+    #[display("relative link to {_0}")]
+    RelativeLink(RelativePathBuf),
+
     // DotMap target is already there but points to a different source
+    #[display("wrong absolute link to {}", _0.display())]
     WrongAbsoluteLink(PathBuf),
+
+    // DotMap target is a symlink where a hard link is wanted
+    #[display("symlink to {}", _0.display())]
+    Symlink(PathBuf),
+
+    // DotMap target is a separate file with the source's contents, where a hard link is wanted,
+    // as left by an editor saving a copy over one of them
+    #[display("stale hard link")]
+    StaleHardLink,
+
+    // DotMap target is a copy of a secret that differs from the decrypted source
+    #[display("changed secret")]
+    ChangedSecret,
+
+    // DotMap target is in place but has other permissions than declared
+    #[display("wrong permissions {_0}")]
+    WrongPermissions(Permissions),
 }
 
 #[derive(Debug, Constructor, PartialEq, Eq, Hash)]
 pub struct DotLink {
+    // Source in the repo the link is for
+    pub source: PathBuf,
+
     // Absolute link to the target
     pub target: PathBuf,
 
-    // Link to create
-    pub link: RelativePathBuf,
+    // Link to create, relative to the target's directory unless the style is absolute
+    pub link: PathBuf,
+
+    // Hard links and secrets always use the absolute source
+    pub mode: LinkMode,
+
+    // Permissions the target must have, which for links are those of the source
+    pub permissions: Option<Permissions>,
 }
 
 pub type DotLinkSet = HashSet<DotLink>;
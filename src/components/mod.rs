@@ -1,3 +1,4 @@
+pub mod doctor;
 pub mod dotzo;
 pub mod environment;
 pub mod linker;
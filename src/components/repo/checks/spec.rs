@@ -0,0 +1,152 @@
+use derive_more::derive::Constructor;
+use log::debug;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{
+    components::repo::directory::{
+        DirVisitation, RepoDirItem, RepoDirItemWithPath, RepoDirVisitorError,
+    },
+    config::{
+        file::Strictness,
+        spec::translate::{SpecContext, SpecContextError},
+    },
+    util::fs::{DirectoryListing, MetadataChecks},
+};
+
+#[derive(Debug, Error)]
+pub enum SpecCheckError {
+    #[error("Io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Error visiting directory: {0}")]
+    DirVisitor(#[from] RepoDirVisitorError),
+}
+
+pub type Result<T> = std::result::Result<T, SpecCheckError>;
+
+#[derive(Debug, Error)]
+pub enum SpecProblemKind {
+    #[error("{0}")]
+    Invalid(SpecContextError),
+
+    #[error("Mapped sources not found: {}", .0.join(", "))]
+    MissingSources(Vec<String>),
+
+    #[error("{0} is a directory, which can't be hard linked")]
+    HardLinkedDirectory(String),
+}
+
+#[derive(Debug, Constructor)]
+pub struct SpecProblem {
+    pub directory: PathBuf,
+    pub kind: SpecProblemKind,
+}
+
+#[derive(Debug)]
+pub struct SpecCheck<'a, MC: MetadataChecks, DL: DirectoryListing> {
+    visitor: DirVisitation<'a, MC, DL>,
+    strictness: Strictness,
+}
+
+impl<'a, MC: MetadataChecks, DL: DirectoryListing> SpecCheck<'a, MC, DL> {
+    pub fn new(metadata_checks: &'a MC, directory_listing: &'a DL, strictness: Strictness) -> Self {
+        Self {
+            visitor: DirVisitation::new(metadata_checks, directory_listing),
+            strictness,
+        }
+    }
+
+    /// Checks every spec reachable from the root, collecting problems rather than stopping
+    pub fn check(&self, root: impl AsRef<Path>) -> Result<Vec<SpecProblem>> {
+        let mut problems = Vec::new();
+        let mut stack = vec![root.as_ref().to_path_buf()];
+        while let Some(current) = stack.pop() {
+            debug!("Checking spec in: {}", current.display());
+            // A broken spec maps nothing, but the specs below it are still checked
+            let context = match SpecContext::from_path(&current, self.strictness) {
+                Ok(context) => context,
+                Err(error) => {
+                    problems.push(SpecProblem::new(
+                        current.clone(),
+                        SpecProblemKind::Invalid(error),
+                    ));
+                    SpecContext::default()
+                }
+            };
+
+            let mut missing: HashSet<&String> = context.targets.keys().collect();
+            for entry in self.visitor.visit(&current, &context)? {
+                let entry = match entry {
+                    Err(RepoDirVisitorError::HardLinkedDirectory(path)) => {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        missing.retain(|source| **source != name);
+                        problems.push(SpecProblem::new(
+                            current.clone(),
+                            SpecProblemKind::HardLinkedDirectory(name.into_owned()),
+                        ));
+                        continue;
+                    }
+                    entry => entry?,
+                };
+                match entry {
+                    RepoDirItemWithPath {
+                        path,
+                        item: RepoDirItem::SubDir,
+                    } => stack.push(path),
+                    RepoDirItemWithPath {
+                        item: RepoDirItem::Mapping(name, _),
+                        ..
+                    } => {
+                        missing.remove(&name);
+                    }
+                    _ => (),
+                }
+            }
+
+            if !missing.is_empty() {
+                let mut missing: Vec<String> = missing.into_iter().cloned().collect();
+                missing.sort();
+                problems.push(SpecProblem::new(
+                    current,
+                    SpecProblemKind::MissingSources(missing),
+                ));
+            }
+        }
+        Ok(problems)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{create_dir, write};
+
+    use super::*;
+    use crate::util::fs::{testing::ScratchDir, StandardFsRead};
+
+    #[test]
+    fn test_specs_below_broken_spec() {
+        let etc = ScratchDir::new("spec-nested");
+        write(etc.join(".dot"), "hom:\n- vimrc\n").unwrap();
+        create_dir(etc.join("tmux")).unwrap();
+        write(etc.join("tmux/.dot"), "home:\n- tmux.conf\n").unwrap();
+
+        let fs = StandardFsRead::new();
+        let problems = SpecCheck::new(&fs, &fs, Strictness::Strict)
+            .check(&*etc)
+            .unwrap();
+        assert_eq!(problems.len(), 2);
+        assert!(matches!(
+            &problems[0],
+            SpecProblem { directory, kind: SpecProblemKind::Invalid(_) } if *directory == *etc
+        ));
+        assert!(matches!(
+            &problems[1],
+            SpecProblem { directory, kind: SpecProblemKind::MissingSources(missing) }
+                if *directory == etc.join("tmux") && *missing == ["tmux.conf"]
+        ));
+    }
+}
@@ -1,5 +1,4 @@
 use derive_more::derive::Constructor;
-use log::error;
 use thiserror::Error;
 
 use crate::{
@@ -2,7 +2,10 @@ use derive_more::derive::Constructor;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
-use crate::{config::spec::translate::SpecContext, mapping::LocatedTarget};
+use crate::{
+    config::spec::translate::SpecContext,
+    mapping::{LinkMode, LocatedTarget},
+};
 
 use crate::util::fs::{DirEntryIterator, DirectoryListing, MetadataChecks};
 
@@ -33,6 +36,9 @@ pub enum RepoDirVisitorError {
 
     #[error("Can't get file name from path: {0:?}")]
     CannotGetFileName(PathBuf),
+
+    #[error("{} is a directory, which can't be hard linked", .0.display())]
+    HardLinkedDirectory(PathBuf),
 }
 
 pub type Result<V> = std::result::Result<V, RepoDirVisitorError>;
@@ -51,18 +57,23 @@ impl<'a, 'b: 'a, ID: DirEntryIterator, MC: MetadataChecks> DirVisitor<'a, 'b, ID
             .and_then(|n| n.to_str())
             .ok_or_else(|| RepoDirVisitorError::CannotGetFileName(path.to_path_buf()))
             .map(ToOwned::to_owned)
-            .map(|file_name| {
-                if file_name.starts_with(".") {
+            .and_then(|file_name| {
+                Ok(if file_name.starts_with(".") {
                     RepoDirItem::Ignore(IgnoreType::Dot, file_name)
                 } else if self.context.ignores.contains(&file_name) {
                     RepoDirItem::Ignore(IgnoreType::Explicit, file_name)
                 } else if let Some(target) = self.context.targets.get(&file_name) {
+                    // Only files can be hard linked
+                    if target.target.mode == LinkMode::Hardlink && self.metadata_checks.is_dir(path)
+                    {
+                        return Err(RepoDirVisitorError::HardLinkedDirectory(path.to_owned()));
+                    }
                     RepoDirItem::Mapping(file_name, target.clone())
                 } else if !self.metadata_checks.is_real_dir(path) {
                     RepoDirItem::Ignore(IgnoreType::Implicit, file_name)
                 } else {
                     RepoDirItem::SubDir
-                }
+                })
             })
     }
 }
@@ -107,7 +118,7 @@ mod test {
     use super::*;
     use crate::{
         config::spec::types::{Mapping, Shorthand, Spec},
-        mapping::Target,
+        mapping::{LinkMode, Target},
         util::fs::{
             testing::{TestFile, TestFs},
             DirEntryResult,
@@ -122,14 +133,21 @@ mod test {
                     source: "source_name".into(),
                     target: Some("target_name".into()),
                     dot: None,
+                    link: None,
+                    mode: None,
+                    permissions: None,
                 }),
             ]),
             Some(vec![Shorthand::Mapped(Mapping {
                 source: "original_name".into(),
                 target: None,
                 dot: Some(false),
+                link: None,
+                mode: None,
+                permissions: None,
             })]),
             Some(["ignore_a".into(), "ignore_b".into()].into_iter().collect()),
+            None,
         )
     });
 
@@ -152,11 +170,12 @@ mod test {
             home: None,
             config: None,
             ignore: None,
+            hooks: None,
         };
         let test_entries = vec![];
         let expected: Vec<RepoDirItemWithPath> = vec![];
 
-        let test_context = SpecContext::new(test_spec);
+        let test_context = SpecContext::new(test_spec).unwrap();
         let visitor = DirVisitor::new(test_entries.into_iter(), &test_context, &*TEST_TREE);
         let result = visitor.collect::<Result<Vec<_>>>();
         assert!(matches!(result, Ok(actual) if actual == expected));
@@ -179,7 +198,7 @@ mod test {
                 RepoDirItem::Mapping(
                     "in_home".into(),
                     LocatedTarget::new(
-                        Target::new("in_home".into(), None),
+                        Target::new("in_home".into(), None, None, LinkMode::Symlink, None),
                         crate::mapping::Destination::Home,
                     ),
                 ),
@@ -194,7 +213,7 @@ mod test {
                 RepoDirItem::Mapping(
                     "source_name".into(),
                     LocatedTarget::new(
-                        Target::new("target_name".into(), None),
+                        Target::new("target_name".into(), None, None, LinkMode::Symlink, None),
                         crate::mapping::Destination::Home,
                     ),
                 ),
@@ -204,7 +223,13 @@ mod test {
                 RepoDirItem::Mapping(
                     "original_name".into(),
                     LocatedTarget::new(
-                        Target::new("original_name".into(), Some(false)),
+                        Target::new(
+                            "original_name".into(),
+                            Some(false),
+                            None,
+                            LinkMode::Symlink,
+                            None,
+                        ),
                         crate::mapping::Destination::Config,
                     ),
                 ),
@@ -216,12 +241,38 @@ mod test {
             ),
         ];
 
-        let test_context = SpecContext::new(TEST_SPEC.clone());
+        let test_context = SpecContext::new(TEST_SPEC.clone()).unwrap();
         let visitor = DirVisitor::new(test_entries.into_iter(), &test_context, &*TEST_TREE);
         let result = visitor.collect::<Result<Vec<_>>>();
         assert!(matches!(result, Ok(actual) if actual == expected));
     }
 
+    #[test]
+    fn test_dir_visitor_hard_linked_directory() {
+        let spec = Spec::new(
+            Some(vec![Shorthand::Mapped(Mapping {
+                source: "dir-x".into(),
+                target: None,
+                dot: None,
+                link: None,
+                mode: Some(LinkMode::Hardlink),
+                permissions: None,
+            })]),
+            None,
+            None,
+            None,
+        );
+        let test_entries: Vec<DirEntryResult> = vec![Ok("path/to/dir-x".into())];
+
+        let test_context = SpecContext::new(spec).unwrap();
+        let visitor = DirVisitor::new(test_entries.into_iter(), &test_context, &*TEST_TREE);
+        let result = visitor.collect::<Result<Vec<_>>>();
+        assert!(matches!(
+            result,
+            Err(RepoDirVisitorError::HardLinkedDirectory(path)) if path == Path::new("path/to/dir-x")
+        ));
+    }
+
     #[test]
     fn test_dir_visitor_error() {
         let test_entries: Vec<DirEntryResult> = vec![
@@ -230,7 +281,7 @@ mod test {
             Err(std::io::Error::other("an io error")),
         ];
 
-        let test_context = SpecContext::new(TEST_SPEC.clone());
+        let test_context = SpecContext::new(TEST_SPEC.clone()).unwrap();
         let visitor = DirVisitor::new(test_entries.into_iter(), &test_context, &*TEST_TREE);
         let result = visitor.collect::<Result<Vec<_>>>();
         assert!(matches!(
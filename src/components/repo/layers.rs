@@ -0,0 +1,160 @@
+use derive_more::derive::Constructor;
+use log::{debug, warn};
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{
+    components::environment::types::Environment,
+    mapping::{DotMap, DotMaps},
+};
+
+use super::types::Repo;
+
+#[derive(Debug, Constructor, Clone, PartialEq, Eq)]
+pub struct LayeredDotMap {
+    // Root of the repo the mapping comes from
+    pub repo: PathBuf,
+
+    // The mapping that won the target
+    pub dot_map: DotMap,
+
+    // Sources from lower priority repos mapping to the same target
+    pub overridden: Vec<PathBuf>,
+}
+
+/// Mappings from all repos keyed by their target path
+pub type LayeredDotMaps = HashMap<PathBuf, LayeredDotMap>;
+
+/// Combines the mappings of each repo, given in priority order. When two mappings resolve to
+/// the same target, the one from the higher priority repo wins. Within a single repo, the
+/// first source in path order wins.
+pub fn layer_dot_maps<'r>(
+    environment: &Environment,
+    layers: impl IntoIterator<Item = (&'r Repo, DotMaps)>,
+) -> LayeredDotMaps {
+    let mut layered = LayeredDotMaps::new();
+    for (repo, dot_maps) in layers {
+        let mut dot_maps: Vec<DotMap> = dot_maps.into_values().collect();
+        dot_maps.sort_by(|a, b| a.source.cmp(&b.source));
+
+        for dot_map in dot_maps {
+            let target = environment.target_path(&dot_map.target);
+            match layered.get_mut(&target) {
+                Some(winner) if winner.repo == repo.path => {
+                    warn!(
+                        "Both {} and {} map to {}, using the first",
+                        winner.dot_map.source.display(),
+                        dot_map.source.display(),
+                        target.display()
+                    );
+                    winner.overridden.push(dot_map.source);
+                }
+                Some(winner) => {
+                    debug!(
+                        "{} overrides {} for {}",
+                        winner.dot_map.source.display(),
+                        dot_map.source.display(),
+                        target.display()
+                    );
+                    winner.overridden.push(dot_map.source);
+                }
+                None => {
+                    layered.insert(
+                        target,
+                        LayeredDotMap::new(repo.path.clone(), dot_map, Vec::new()),
+                    );
+                }
+            }
+        }
+    }
+    layered
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::LazyLock;
+
+    use super::*;
+    use crate::mapping::{Destination, LinkMode, Target};
+
+    static TEST_ENVIRONMENT: LazyLock<Environment> = LazyLock::new(|| {
+        Environment::new(
+            PathBuf::from("/home").into(),
+            PathBuf::from("/home/.config").into(),
+            PathBuf::from("/home/.local/share").into(),
+            PathBuf::from("/home/.local/state").into(),
+            PathBuf::from("/home/.cache").into(),
+        )
+    });
+
+    fn dot_maps<const N: usize>(maps: [(&str, &str, Destination); N]) -> DotMaps {
+        maps.into_iter()
+            .map(|(source, name, destination)| {
+                let dot_map = DotMap::new(
+                    PathBuf::from(source),
+                    destination.locate(Target::new(
+                        name.into(),
+                        None,
+                        None,
+                        LinkMode::Symlink,
+                        None,
+                    )),
+                );
+                (PathBuf::from(source), dot_map)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_higher_priority_repo_wins() {
+        let personal = Repo::new(PathBuf::from("/home/_"), None);
+        let team = Repo::new(PathBuf::from("/home/team"), None);
+        let layered = layer_dot_maps(
+            &TEST_ENVIRONMENT,
+            [
+                (
+                    &personal,
+                    dot_maps([("/home/_/etc/vimrc", "vimrc", Destination::Home)]),
+                ),
+                (
+                    &team,
+                    dot_maps([
+                        ("/home/team/etc/vimrc", "vimrc", Destination::Home),
+                        ("/home/team/etc/nvim", "nvim", Destination::Config),
+                    ]),
+                ),
+            ],
+        );
+
+        assert_eq!(layered.len(), 2);
+        let vimrc = &layered[&PathBuf::from("/home/.vimrc")];
+        assert_eq!(vimrc.repo, personal.path);
+        assert_eq!(vimrc.dot_map.source, PathBuf::from("/home/_/etc/vimrc"));
+        assert_eq!(
+            vimrc.overridden,
+            vec![PathBuf::from("/home/team/etc/vimrc")]
+        );
+
+        let nvim = &layered[&PathBuf::from("/home/.config/nvim")];
+        assert_eq!(nvim.repo, team.path);
+        assert!(nvim.overridden.is_empty());
+    }
+
+    #[test]
+    fn test_same_repo_collision_is_deterministic() {
+        let repo = Repo::new(PathBuf::from("/home/_"), None);
+        let layered = layer_dot_maps(
+            &TEST_ENVIRONMENT,
+            [(
+                &repo,
+                dot_maps([
+                    ("/home/_/etc/b/vimrc", "vimrc", Destination::Home),
+                    ("/home/_/etc/a/vimrc", "vimrc", Destination::Home),
+                ]),
+            )],
+        );
+
+        let vimrc = &layered[&PathBuf::from("/home/.vimrc")];
+        assert_eq!(vimrc.dot_map.source, PathBuf::from("/home/_/etc/a/vimrc"));
+        assert_eq!(vimrc.overridden, vec![PathBuf::from("/home/_/etc/b/vimrc")]);
+    }
+}
@@ -1,4 +1,7 @@
 pub mod checks;
 pub mod directory;
+pub mod layers;
+pub mod selection;
+pub mod state;
 pub mod tree;
 pub mod types;
@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+
+use super::{
+    layers::{LayeredDotMap, LayeredDotMaps},
+    types::Repo,
+};
+use crate::util::fs::normalize;
+
+/// Paths given on the command line, each naming a source in a repo or a target, a path in
+/// one, or a directory containing them that is in a repo or below a destination directory
+#[derive(Debug)]
+pub struct PathSelection {
+    // Each given path with the absolute paths it could refer to, in the order they are tried
+    paths: Vec<(PathBuf, Vec<PathBuf>)>,
+    repos: Vec<PathBuf>,
+    // The destination directories, which a path may be below but not hold
+    roots: Vec<PathBuf>,
+}
+
+impl PathSelection {
+    /// Relative paths are tried against the current directory, then the root of each repo
+    pub fn new(paths: &[PathBuf], current_dir: &Path, repos: &[Repo], roots: &[&Path]) -> Self {
+        let paths = paths
+            .iter()
+            .map(|path| {
+                let candidates = if path.is_absolute() {
+                    vec![normalize(path)]
+                } else {
+                    std::iter::once(current_dir)
+                        .chain(repos.iter().map(|repo| repo.path.as_path()))
+                        .map(|base| normalize(&base.join(path)))
+                        .collect()
+                };
+                (path.clone(), candidates)
+            })
+            .collect();
+        Self {
+            paths,
+            repos: repos.iter().map(|repo| repo.path.clone()).collect(),
+            roots: roots.iter().map(|root| root.to_path_buf()).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    fn holds_root(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| root.starts_with(path))
+    }
+
+    /// The given paths that hold a whole destination directory, such as home itself, which
+    /// would select every mapping into it
+    pub fn too_broad(&self) -> Vec<PathBuf> {
+        self.paths
+            .iter()
+            .filter(|(_, candidates)| candidates.first().is_some_and(|c| self.holds_root(c)))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    fn matches(&self, candidate: &Path, target: &Path, layered: &LayeredDotMap) -> bool {
+        let source = &layered.dot_map.source;
+        candidate.starts_with(source)
+            || candidate.starts_with(target)
+            || (source.starts_with(candidate)
+                && self.repos.iter().any(|repo| candidate.starts_with(repo)))
+            || (target.starts_with(candidate) && !self.holds_root(candidate))
+    }
+
+    // The first of the paths a given path could refer to that selects anything
+    fn resolve<'p>(
+        &self,
+        candidates: &'p [PathBuf],
+        dot_maps: &LayeredDotMaps,
+    ) -> Option<&'p Path> {
+        candidates
+            .iter()
+            .find(|candidate| {
+                dot_maps
+                    .iter()
+                    .any(|(target, layered)| self.matches(candidate, target, layered))
+            })
+            .map(PathBuf::as_path)
+    }
+
+    /// Keeps only the mappings selected by some path, returning the paths that selected nothing
+    pub fn retain(&self, dot_maps: &mut LayeredDotMaps) -> Vec<PathBuf> {
+        let mut resolved = Vec::new();
+        let mut unmatched = Vec::new();
+        for (path, candidates) in &self.paths {
+            match self.resolve(candidates, dot_maps) {
+                Some(candidate) => resolved.push(candidate),
+                None => unmatched.push(path.clone()),
+            }
+        }
+
+        dot_maps.retain(|target, layered| {
+            resolved
+                .iter()
+                .any(|candidate| self.matches(candidate, target, layered))
+        });
+        unmatched
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mapping::{Destination, DotMap, LinkMode, Target};
+
+    fn layered_dot_maps() -> LayeredDotMaps {
+        [
+            (
+                "/home/_/etc/tmux/tmux.conf",
+                "/home/.tmux.conf",
+                "tmux.conf",
+            ),
+            ("/home/_/etc/nvim", "/home/.config/nvim", "nvim"),
+            ("/home/_/etc/vimrc", "/home/.vimrc", "vimrc"),
+        ]
+        .into_iter()
+        .map(|(source, target, name)| {
+            let dot_map = DotMap::new(
+                PathBuf::from(source),
+                Destination::Home.locate(Target::new(
+                    name.into(),
+                    None,
+                    None,
+                    LinkMode::Symlink,
+                    None,
+                )),
+            );
+            (
+                PathBuf::from(target),
+                LayeredDotMap::new(PathBuf::from("/home/_"), dot_map, Vec::new()),
+            )
+        })
+        .collect()
+    }
+
+    fn selection(paths: &[&str], current_dir: &str) -> PathSelection {
+        let repos = [Repo::new(PathBuf::from("/home/_"), None)];
+        let roots = [Path::new("/home"), Path::new("/home/.config")];
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        PathSelection::new(&paths, Path::new(current_dir), &repos, &roots)
+    }
+
+    fn selected(paths: &[&str], current_dir: &str) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let selection = selection(paths, current_dir);
+        let mut dot_maps = layered_dot_maps();
+        let unmatched = selection.retain(&mut dot_maps);
+        let mut targets: Vec<PathBuf> = dot_maps.into_keys().collect();
+        targets.sort();
+        (targets, unmatched)
+    }
+
+    #[test]
+    fn test_select_by_repo_path() {
+        let (targets, unmatched) = selected(&["etc/tmux"], "/tmp");
+        assert_eq!(targets, vec![PathBuf::from("/home/.tmux.conf")]);
+        assert!(unmatched.is_empty());
+    }
+
+    #[test]
+    fn test_select_by_target_and_relative_path() {
+        let (targets, _) = selected(
+            &["/home/.config/nvim/init.lua", "../vimrc"],
+            "/home/_/etc/nvim",
+        );
+        assert_eq!(
+            targets,
+            vec![
+                PathBuf::from("/home/.config/nvim"),
+                PathBuf::from("/home/.vimrc")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unmatched_paths_reported() {
+        let (targets, unmatched) = selected(&["etc/missing"], "/tmp");
+        assert!(targets.is_empty());
+        assert_eq!(unmatched, vec![PathBuf::from("etc/missing")]);
+    }
+
+    #[test]
+    fn test_select_by_repo_directory() {
+        let (targets, _) = selected(&["/home/_/etc"], "/tmp");
+        assert_eq!(targets.len(), 3);
+    }
+
+    #[test]
+    fn test_home_is_too_broad() {
+        assert_eq!(
+            selection(&["/home"], "/tmp").too_broad(),
+            vec![PathBuf::from("/home")]
+        );
+        assert_eq!(
+            selection(&["."], "/home").too_broad(),
+            vec![PathBuf::from(".")]
+        );
+        assert_eq!(
+            selection(&["/home/.config"], "/tmp").too_broad(),
+            vec![PathBuf::from("/home/.config")]
+        );
+        assert!(selection(&["/home/.config/nvim"], "/tmp")
+            .too_broad()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_directory_outside_repos_and_destinations_selects_nothing() {
+        let (targets, unmatched) = selected(&["/srv"], "/tmp");
+        assert!(targets.is_empty());
+        assert_eq!(unmatched, vec![PathBuf::from("/srv")]);
+    }
+}
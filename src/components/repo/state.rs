@@ -0,0 +1,62 @@
+use std::fmt;
+
+use crate::util::git::{BranchInfo, Error as GitError, Git, StatusEntry};
+
+use super::types::Repo;
+
+/// Working tree state of a repo as reported by git
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoState {
+    pub branch: BranchInfo,
+
+    // Uncommitted changes under etc
+    pub dirty: Vec<StatusEntry>,
+}
+
+impl RepoState {
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+}
+
+impl fmt::Display for RepoState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.branch.head {
+            Some(head) => write!(f, "on {}", head)?,
+            None => write!(f, "detached")?,
+        }
+        match &self.branch.upstream {
+            Some(upstream) => write!(
+                f,
+                ", {} ahead and {} behind {}",
+                self.branch.ahead, self.branch.behind, upstream
+            )?,
+            None => write!(f, ", no upstream")?,
+        }
+        match self.dirty.len() {
+            0 => write!(f, ", clean"),
+            n => write!(f, ", {} uncommitted changes under etc", n),
+        }
+    }
+}
+
+impl Repo {
+    pub fn state<G: Git>(&self, git: &G) -> Result<RepoState, GitError> {
+        let branch = git.branch(&self.path)?;
+        let dirty = self.etc_changes(git, git.status(&self.path)?)?;
+        Ok(RepoState { branch, dirty })
+    }
+
+    /// Keeps the status entries under etc, the repo possibly being nested in its work tree
+    pub fn etc_changes<G: Git>(
+        &self,
+        git: &G,
+        status: Vec<StatusEntry>,
+    ) -> Result<Vec<StatusEntry>, GitError> {
+        let etc = git.prefix(&self.path)?.join("etc");
+        Ok(status
+            .into_iter()
+            .filter(|entry| entry.path.starts_with(&etc))
+            .collect())
+    }
+}
@@ -3,14 +3,27 @@ use tryiter::TryIteratorExt;
 
 use log::{debug, warn};
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
 };
 use thiserror::Error;
 
 use crate::{
-    components::repo::directory::{DirVisitation, IgnoreType, RepoDirItem, RepoDirItemWithPath},
-    config::{file::ConfigFileReadError, spec::translate::SpecContext},
+    components::{
+        environment::types::Environment,
+        repo::{
+            directory::{DirVisitation, IgnoreType, RepoDirItem, RepoDirItemWithPath},
+            layers::{layer_dot_maps, LayeredDotMaps},
+            types::Repo,
+        },
+    },
+    config::{
+        file::Strictness,
+        spec::{
+            translate::{SpecContext, SpecContextError},
+            types::Hooks,
+        },
+    },
     mapping::{DotMap, DotMaps},
     util::fs::{DirectoryListing, MetadataChecks},
 };
@@ -58,13 +71,28 @@ impl DirData {
     }
 }
 
+/// Hooks declared by each directory's spec, keyed by the directory
+pub type DirHooks = HashMap<PathBuf, Hooks>;
+
+#[derive(Debug, Default)]
+pub struct RepoTree {
+    pub dot_maps: DotMaps,
+    pub hooks: DirHooks,
+}
+
+#[derive(Debug, Default)]
+pub struct LayeredTree {
+    pub dot_maps: LayeredDotMaps,
+    pub hooks: DirHooks,
+}
+
 #[derive(Debug, Error)]
 pub enum TreeTraverserError {
     #[error("Io error: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("Error reading config file: {0}")]
-    ConfigFile(#[from] ConfigFileReadError),
+    #[error("Error reading spec: {0}")]
+    Spec(#[from] SpecContextError),
 
     #[error("Error visiting directory: {0}")]
     DirVisitor(#[from] RepoDirVisitorError),
@@ -75,6 +103,7 @@ pub type Result<T> = core::result::Result<T, TreeTraverserError>;
 #[derive(Debug)]
 pub struct TreeTraverser<'a, DL: DirectoryListing, MC: MetadataChecks> {
     visitor: DirVisitation<'a, MC, DL>,
+    strictness: Strictness,
 }
 
 #[derive(Constructor)]
@@ -109,18 +138,20 @@ impl DirectoryItemConsumer<'_, '_> {
 }
 
 impl<'a, DL: DirectoryListing, MC: MetadataChecks> TreeTraverser<'a, DL, MC> {
-    pub fn new(metadata_checks: &'a MC, directory_listing: &'a DL) -> Self {
+    pub fn new(metadata_checks: &'a MC, directory_listing: &'a DL, strictness: Strictness) -> Self {
         Self {
             visitor: DirVisitation::new(metadata_checks, directory_listing),
+            strictness,
         }
     }
 
-    pub fn traverse(&self, root: impl AsRef<Path>) -> Result<DotMaps> {
+    pub fn traverse(&self, root: impl AsRef<Path>) -> Result<RepoTree> {
         let mut mapping: DotMaps = Default::default();
+        let mut hooks: DirHooks = Default::default();
         let mut stack = vec![root.as_ref().to_path_buf()];
         while let Some(current) = stack.pop() {
             debug!("Visiting directory: {:?}", current);
-            let context = SpecContext::from_path(&current)?;
+            let context = SpecContext::from_path(&current, self.strictness)?;
             let mut dir_data = DirData::new(current.clone(), &context);
             let mut consumer = DirectoryItemConsumer::new(&mut mapping, &mut dir_data);
             self.visitor
@@ -128,8 +159,33 @@ impl<'a, DL: DirectoryListing, MC: MetadataChecks> TreeTraverser<'a, DL, MC> {
                 .try_filter_map(|item| Ok(consumer.consume(item)))
                 .try_for_each(|entry| entry.map(|path| stack.push(path)))?;
             dir_data.report();
+            if !context.hooks.is_empty() {
+                hooks.insert(current, context.hooks);
+            }
         }
 
-        Ok(mapping)
+        Ok(RepoTree {
+            dot_maps: mapping,
+            hooks,
+        })
+    }
+
+    /// Traverses each repo, given in priority order, and layers their mappings
+    pub fn traverse_layered(
+        &self,
+        environment: &Environment,
+        repos: &[Repo],
+    ) -> Result<LayeredTree> {
+        let mut hooks = DirHooks::new();
+        let mut layers = Vec::new();
+        for repo in repos {
+            let tree = self.traverse(repo.etc())?;
+            hooks.extend(tree.hooks);
+            layers.push((repo, tree.dot_maps));
+        }
+        Ok(LayeredTree {
+            dot_maps: layer_dot_maps(environment, layers),
+            hooks,
+        })
     }
 }
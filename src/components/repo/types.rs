@@ -1,11 +1,15 @@
 use derive_more::derive::Constructor;
 use std::path::{Path, PathBuf};
 
-use crate::{components::environment::types::Environment, config::rc::types::Rc};
+use crate::{
+    components::environment::types::Environment,
+    config::rc::types::{Rc, Remote},
+};
 
 #[derive(Debug, Constructor, PartialEq, Eq)]
 pub struct Repo {
     pub path: PathBuf,
+    pub remote: Option<Remote>,
 }
 
 impl AsRef<Path> for Repo {
@@ -15,11 +19,33 @@ impl AsRef<Path> for Repo {
 }
 
 impl Repo {
-    pub fn from_config(environment: &Environment, rc: &Rc, given: Option<PathBuf>) -> Self {
-        Self::new(given.unwrap_or_else(|| rc.repo.location.to_path(&environment.home)))
+    /// All repos in priority order, or just the given one if there is an override
+    pub fn all_from_config(
+        environment: &Environment,
+        rc: &Rc,
+        given: Option<PathBuf>,
+    ) -> Vec<Self> {
+        match given {
+            Some(path) => vec![Self::new(path, None)],
+            None => rc
+                .all_repos()
+                .map(|repo| {
+                    Self::new(
+                        repo.location.to_path(&environment.home),
+                        repo.remote
+                            .as_ref()
+                            .map(|remote| remote.resolve(environment.home.as_ref())),
+                    )
+                })
+                .collect(),
+        }
     }
 
     pub fn etc(&self) -> PathBuf {
         self.path.join("etc")
     }
+
+    pub fn bin(&self) -> PathBuf {
+        self.path.join("bin")
+    }
 }
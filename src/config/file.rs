@@ -1,9 +1,9 @@
 use derive_more::derive::Constructor;
-use log::debug;
-use serde::de::DeserializeOwned;
+use log::{debug, warn};
+use serde::{de::DeserializeOwned, Serialize};
 use std::{
     fs::File,
-    io::{BufReader, ErrorKind},
+    io::{BufReader, ErrorKind, Read},
     path::{Path, PathBuf},
 };
 use thiserror::Error;
@@ -30,10 +30,22 @@ pub struct ConfigFilePath {
 }
 
 pub struct ConfigFile {
+    path: PathBuf,
     file: File,
     format: ConfigFormat,
 }
 
+/// How to treat keys in a config file that do not correspond to any field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Unknown keys are an error
+    #[default]
+    Strict,
+
+    /// Unknown keys are reported as warnings and otherwise ignored
+    Lenient,
+}
+
 #[derive(Debug, Error)]
 pub enum ConfigFileReadError {
     #[error("IO error reading config file")]
@@ -44,10 +56,42 @@ pub enum ConfigFileReadError {
 
     #[error("Error parsing config file")]
     YamlParsing(#[from] serde_yaml::Error),
+
+    #[error("Unknown keys in {}: {}", path.display(), keys.join(", "))]
+    UnknownKeys { path: PathBuf, keys: Vec<String> },
 }
 
 pub type Result<T> = std::result::Result<T, ConfigFileReadError>;
 
+#[derive(Debug, Error)]
+pub enum ConfigFileWriteError {
+    #[error("Error serializing config file")]
+    JsonSerializing(#[from] serde_json::Error),
+
+    #[error("Error serializing config file")]
+    YamlSerializing(#[from] serde_yaml::Error),
+}
+
+/// Formats the path to a key, leaving out the option and newtype wrappers
+fn key_path(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, index } => join_key_path(parent, index),
+        Path::Map { parent, key } => join_key_path(parent, key),
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => key_path(parent),
+    }
+}
+
+fn join_key_path(parent: &serde_ignored::Path, key: impl std::fmt::Display) -> String {
+    match key_path(parent) {
+        prefix if prefix.is_empty() => key.to_string(),
+        prefix => format!("{}.{}", prefix, key),
+    }
+}
+
 fn try_open_file(file_path: impl AsRef<Path>) -> Result<Option<File>> {
     File::open(file_path).map(Some).or_else(|e| {
         if ErrorKind::NotFound == e.kind() {
@@ -66,6 +110,37 @@ impl ConfigFormat {
             _ => None,
         }
     }
+
+    /// Deserializes from the reader, collecting the paths of any keys that were not used
+    pub fn read_with_unknown<C: DeserializeOwned>(
+        self,
+        reader: impl Read,
+    ) -> Result<(C, Vec<String>)> {
+        let mut unknown = Vec::new();
+        let config = match self {
+            ConfigFormat::Json => serde_ignored::deserialize(
+                &mut serde_json::Deserializer::from_reader(reader),
+                |p| unknown.push(key_path(&p)),
+            )?,
+            ConfigFormat::Yaml => {
+                serde_ignored::deserialize(serde_yaml::Deserializer::from_reader(reader), |p| {
+                    unknown.push(key_path(&p))
+                })?
+            }
+        };
+        Ok((config, unknown))
+    }
+
+    /// Serializes the config in this format, ready to be written to a file
+    pub fn write<C: Serialize>(
+        self,
+        config: &C,
+    ) -> std::result::Result<String, ConfigFileWriteError> {
+        Ok(match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(config)? + "\n",
+            ConfigFormat::Yaml => serde_yaml::to_string(config)?,
+        })
+    }
 }
 
 // There are a couple APIs here that are not currently used, but I would like to
@@ -100,7 +175,11 @@ impl ConfigType {
             debug!("Looking for config file at: {}", file_path.display());
             if let Some(file) = try_open_file(&file_path)? {
                 debug!("Found config file at: {}", file_path.display());
-                return Ok(Some(ConfigFile { format, file }));
+                return Ok(Some(ConfigFile {
+                    path: file_path,
+                    format,
+                    file,
+                }));
             }
         }
         debug!("No config file found in: {}", path.display());
@@ -112,6 +191,7 @@ impl ConfigType {
         path.extension()
             .and_then(|ext| ext.to_str())
             .and_then(ConfigFormat::from_extension)
+            .or(self.default_format)
             .map(|format| ConfigFilePath {
                 format,
                 path: path.into(),
@@ -122,13 +202,21 @@ impl ConfigType {
         match self.override_config_file(path) {
             None => Ok(None),
             Some(ConfigFilePath { format, path }) => {
-                try_open_file(path).map(|m| m.map(|file| ConfigFile { format, file }))
+                try_open_file(&path).map(|m| m.map(|file| ConfigFile { path, format, file }))
             }
         }
     }
 }
 
 impl ConfigFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn format(&self) -> ConfigFormat {
+        self.format
+    }
+
     pub fn read_config<C: DeserializeOwned>(&self) -> Result<C> {
         let reader = BufReader::new(&self.file);
         match self.format {
@@ -140,12 +228,34 @@ impl ConfigFile {
             }
         }
     }
+
+    /// Reads the config, treating unknown keys according to the given strictness
+    pub fn read_config_checked<C: DeserializeOwned>(&self, strictness: Strictness) -> Result<C> {
+        let (config, unknown) = self.format.read_with_unknown(BufReader::new(&self.file))?;
+        if unknown.is_empty() {
+            return Ok(config);
+        }
+
+        match strictness {
+            Strictness::Strict => Err(ConfigFileReadError::UnknownKeys {
+                path: self.path.clone(),
+                keys: unknown,
+            }),
+            Strictness::Lenient => {
+                warn!(
+                    "Ignoring unknown keys in {}: {}",
+                    self.path.display(),
+                    unknown.join(", ")
+                );
+                Ok(config)
+            }
+        }
+    }
 }
 
 pub trait ReadFromConfig: DeserializeOwned {
     fn config_type() -> ConfigType;
 
-    #[allow(dead_code)]
     fn read_from_path(path: impl AsRef<Path>) -> Result<Option<Self>> {
         Self::config_type()
             .get_config_file(path)
@@ -157,4 +267,13 @@ pub trait ReadFromConfig: DeserializeOwned {
             .find_config_file(path)
             .and_then(|m| m.map(|p| p.read_config()).transpose())
     }
+
+    fn find_in_path_checked(
+        path: impl AsRef<Path>,
+        strictness: Strictness,
+    ) -> Result<Option<Self>> {
+        Self::config_type()
+            .find_config_file(path)
+            .and_then(|m| m.map(|p| p.read_config_checked(strictness)).transpose())
+    }
 }
@@ -1,3 +1,4 @@
 pub mod file;
 pub mod rc;
+pub mod schema;
 pub mod spec;
@@ -1,39 +1,171 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use derive_more::derive::Constructor;
 use relative_path::RelativePathBuf;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use crate::config::file::{ConfigType, ReadFromConfig};
+use crate::{
+    config::file::{ConfigType, ReadFromConfig},
+    mapping::{LinkStyle, Permissions},
+};
 
-#[derive(Debug, Constructor, PartialEq, Eq, Clone, Serialize, Deserialize)]
-pub struct Remote(
-    // TODO: Upgrade to validated URI object
-    String,
-);
+const URL_SCHEMES: &[&str] = &["http", "https", "ssh", "git", "file"];
 
-#[derive(Debug, Constructor, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Remote {0:?} is not a URL, an scp-like address, or a local path")]
+pub struct InvalidRemote(String);
+
+/// Where a repo can be cloned from, as accepted by git
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+pub struct Remote(String);
+
+impl Remote {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // Local paths starting ./ or ../ are relative to home, like the repo location
+    fn is_relative(remote: &str) -> bool {
+        remote.starts_with("./") || remote.starts_with("../")
+    }
+
+    /// The remote as git is given it, with a relative path resolved against home
+    pub fn resolve(&self, home: &Path) -> Self {
+        match Self::is_relative(&self.0) {
+            true => Self(home.join(&self.0).to_string_lossy().into_owned()),
+            false => self.clone(),
+        }
+    }
+
+    fn is_valid(remote: &str) -> bool {
+        if remote.is_empty() || remote.chars().any(char::is_whitespace) {
+            return false;
+        }
+
+        if let Some((scheme, rest)) = remote.split_once("://") {
+            // URL such as https://github.com/me/dotfiles
+            URL_SCHEMES.contains(&scheme) && !rest.is_empty()
+        } else if remote.starts_with('/') || Self::is_relative(remote) {
+            // Local path, possibly a bare repo
+            true
+        } else if let Some((host, path)) = remote.split_once(':') {
+            // scp-like address such as git@github.com:me/dotfiles
+            !host.is_empty() && !host.contains('/') && !path.is_empty()
+        } else {
+            false
+        }
+    }
+}
+
+impl TryFrom<String> for Remote {
+    type Error = InvalidRemote;
+
+    fn try_from(remote: String) -> Result<Self, Self::Error> {
+        if Self::is_valid(&remote) {
+            Ok(Self(remote))
+        } else {
+            Err(InvalidRemote(remote))
+        }
+    }
+}
+
+impl From<Remote> for String {
+    fn from(remote: Remote) -> Self {
+        remote.0
+    }
+}
+
+#[derive(Debug, Constructor, PartialEq, Eq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Repo {
+    /// Location of the repo relative to home
+    #[schemars(with = "String")]
     pub location: RelativePathBuf,
+    /// Remote the repo is cloned from: a URL, an scp-like address such as
+    /// git@github.com:me/dotfiles, or a local path, which is relative to home when it starts
+    /// with ./ or ../
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub remote: Option<Remote>,
 }
 
-#[derive(Debug, Constructor, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// What to do when syncing from a repo with uncommitted changes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DirtyRepo {
+    Ignore,
+    #[default]
+    Warn,
+    Refuse,
+}
+
+#[derive(Debug, Constructor, PartialEq, Eq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Rc {
-    pub repo: Repo,
+    /// The primary repo, taking priority over any in `repos`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<Repo>,
+    /// Further repos in priority order, the first taking priority over the rest
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repos: Vec<Repo>,
+    /// What to do when syncing from a repo with uncommitted changes
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub dirty_repo: DirtyRepo,
+    /// Age key file for decrypting secrets, relative to home
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub secret_key: Option<RelativePathBuf>,
+    /// Permissions for the directories dotzo creates, such as "0700"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory_permissions: Option<Permissions>,
+    /// How links refer to their sources, unless a mapping says otherwise
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub link: LinkStyle,
+    /// How many directories deep to look for dangling links under home and the config dir
+    #[serde(
+        default = "default_scan_depth",
+        skip_serializing_if = "is_default_scan_depth"
+    )]
+    pub scan_depth: usize,
+}
+
+fn default_scan_depth() -> usize {
+    3
+}
+
+// Keeps settings left at their defaults out of a rewritten rc file
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+fn is_default_scan_depth(depth: &usize) -> bool {
+    *depth == default_scan_depth()
 }
 
 impl Default for Rc {
     fn default() -> Self {
         Self {
-            repo: Repo {
+            repo: Some(Repo {
                 location: RelativePathBuf::from("_"),
                 remote: None,
-            },
+            }),
+            repos: Vec::new(),
+            dirty_repo: DirtyRepo::default(),
+            secret_key: None,
+            directory_permissions: None,
+            link: LinkStyle::default(),
+            scan_depth: default_scan_depth(),
         }
     }
 }
 
+impl Rc {
+    /// All configured repos, highest priority first
+    pub fn all_repos(&self) -> impl Iterator<Item = &Repo> {
+        self.repo.iter().chain(self.repos.iter())
+    }
+}
+
 impl ReadFromConfig for Rc {
     fn config_type() -> ConfigType {
         ConfigType::default_yaml(PathBuf::from(".dotrc"))
@@ -43,6 +175,7 @@ impl ReadFromConfig for Rc {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::file::ConfigFormat;
     use indoc::indoc;
 
     #[test]
@@ -55,8 +188,107 @@ mod tests {
         "#};
         let expected = Repo::new(
             RelativePathBuf::from("_"),
-            Some(Remote::new("http://github.com/my/remote".into())),
+            Some(Remote::try_from("http://github.com/my/remote".to_string()).unwrap()),
         );
         assert_eq!(expected, serde_json::from_str(doc).unwrap());
     }
+
+    #[test]
+    fn test_valid_remotes() {
+        for remote in [
+            "https://github.com/my/remote.git",
+            "ssh://git@github.com/my/remote",
+            "file:///srv/git/dotfiles.git",
+            "git@github.com:my/remote.git",
+            "/srv/git/dotfiles.git",
+            "../dotfiles.git",
+            "./backup/dotfiles",
+        ] {
+            assert!(Remote::try_from(remote.to_string()).is_ok(), "{}", remote);
+        }
+    }
+
+    #[test]
+    fn test_resolve_relative_remote() {
+        let home = Path::new("/home/me");
+        let remote = |remote: &str| Remote::try_from(remote.to_string()).unwrap();
+        assert_eq!(
+            remote("../dotfiles.git").resolve(home),
+            remote("/home/me/../dotfiles.git")
+        );
+        assert_eq!(
+            remote("git@github.com:my/remote.git").resolve(home),
+            remote("git@github.com:my/remote.git")
+        );
+    }
+
+    #[test]
+    fn test_invalid_remotes() {
+        for remote in [
+            "",
+            "dotfiles",
+            "..dotfiles",
+            "ftp://example.com/dotfiles",
+            "https://",
+            "git@github.com:",
+            "http://github.com/my remote",
+        ] {
+            assert!(Remote::try_from(remote.to_string()).is_err(), "{}", remote);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_invalid_remote() {
+        let doc = indoc! {r#"
+            {
+                "location": "_",
+                "remote": "not a remote"
+            }
+        "#};
+        assert!(serde_json::from_str::<Repo>(doc).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_repos_in_priority_order() {
+        let doc = indoc! {r#"
+            repo:
+              location: _
+            repos:
+              - location: src/team-dotfiles
+                remote: http://github.com/team/dotfiles
+              - location: src/shared
+        "#};
+        let rc: Rc = serde_yaml::from_str(doc).unwrap();
+        let locations: Vec<_> = rc.all_repos().map(|r| r.location.as_str()).collect();
+        assert_eq!(locations, vec!["_", "src/team-dotfiles", "src/shared"]);
+    }
+
+    #[test]
+    fn test_deserialize_repos_only() {
+        let doc = indoc! {r#"
+            repos:
+              - location: personal
+              - location: team
+        "#};
+        let rc: Rc = serde_yaml::from_str(doc).unwrap();
+        assert_eq!(rc.repo, None);
+        let locations: Vec<_> = rc.all_repos().map(|r| r.location.as_str()).collect();
+        assert_eq!(locations, vec!["personal", "team"]);
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let doc = indoc! {r#"
+            repo:
+              location: src/dotfiles
+            repos:
+            - location: src/team-dotfiles
+              remote: http://github.com/team/dotfiles
+            dirty_repo: refuse
+        "#};
+        let rc: Rc = serde_yaml::from_str(doc).unwrap();
+        let written = ConfigFormat::Yaml.write(&rc).unwrap();
+        assert_eq!(written, doc);
+        assert_eq!(rc, serde_yaml::from_str(&written).unwrap());
+    }
 }
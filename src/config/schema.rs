@@ -0,0 +1,24 @@
+use clap::ValueEnum;
+use schemars::{schema::RootSchema, schema_for};
+
+use super::{rc::types::Rc, spec::types::Spec};
+
+/// The config files a JSON Schema can be generated for
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum SchemaKind {
+    /// Schema for .dot spec files
+    #[default]
+    Spec,
+
+    /// Schema for the .dotrc file
+    Rc,
+}
+
+impl SchemaKind {
+    pub fn schema(&self) -> RootSchema {
+        match self {
+            SchemaKind::Spec => schema_for!(Spec),
+            SchemaKind::Rc => schema_for!(Rc),
+        }
+    }
+}
@@ -1,36 +1,69 @@
 use std::{collections::HashSet, path::Path};
 
 use log::debug;
+use thiserror::Error;
 
 use crate::{
-    config::file::{ReadFromConfig, Result},
+    config::file::{ConfigFileReadError, ReadFromConfig, Strictness},
     mapping::{Destination, Target, TargetMap},
 };
 
-use super::types::{Mapping, Section, Shorthand, Spec};
+use super::types::{Hooks, Mapping, Section, Shorthand, Spec};
 
-fn map_targets(sections: impl IntoIterator<Item = (Section<Shorthand>, Destination)>) -> TargetMap {
-    sections
-        .into_iter()
-        .flat_map(|(comp, dest)| {
-            comp.unwrap_or_default().into_iter().map(Mapping::from).map(
-                move |Mapping {
-                          source,
-                          target,
-                          dot,
-                      }| {
-                    let target_filled = Target::new(target.unwrap_or_else(|| source.clone()), dot);
-                    (source, dest.locate(target_filled))
-                },
-            )
-        })
-        .collect()
+#[derive(Debug, Error)]
+pub enum SpecContextError {
+    #[error("Error reading spec: {0}")]
+    ConfigFile(#[from] ConfigFileReadError),
+
+    #[error("Source {source_name} is mapped to both {first:?} and {second:?}")]
+    DuplicateSource {
+        source_name: String,
+        first: Destination,
+        second: Destination,
+    },
 }
 
-#[derive(Debug, PartialEq, Eq)]
+pub type Result<T> = core::result::Result<T, SpecContextError>;
+
+fn map_targets(
+    sections: impl IntoIterator<Item = (Section<Shorthand>, Destination)>,
+) -> Result<TargetMap> {
+    let mut targets = TargetMap::new();
+    for (comp, dest) in sections {
+        for Mapping {
+            source,
+            target,
+            dot,
+            link,
+            mode,
+            permissions,
+        } in comp.unwrap_or_default().into_iter().map(Mapping::from)
+        {
+            if let Some(existing) = targets.get(&source) {
+                return Err(SpecContextError::DuplicateSource {
+                    source_name: source,
+                    first: existing.destination.clone(),
+                    second: dest,
+                });
+            }
+            let target_filled = Target::new(
+                target.unwrap_or_else(|| source.clone()),
+                dot,
+                link,
+                mode.unwrap_or_default(),
+                permissions,
+            );
+            targets.insert(source, dest.locate(target_filled));
+        }
+    }
+    Ok(targets)
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
 pub struct SpecContext {
     pub targets: TargetMap,
     pub ignores: HashSet<String>,
+    pub hooks: Hooks,
 }
 
 impl SpecContext {
@@ -39,29 +72,107 @@ impl SpecContext {
             home,
             config,
             ignore,
+            hooks,
         }: Spec,
-    ) -> Self {
-        Self {
-            targets: map_targets([(home, Destination::Home), (config, Destination::Config)]),
+    ) -> Result<Self> {
+        Ok(Self {
+            targets: map_targets([(home, Destination::Home), (config, Destination::Config)])?,
             ignores: ignore.unwrap_or_default(),
-        }
+            hooks: hooks.unwrap_or_default(),
+        })
     }
 
-    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+    pub fn from_path(path: impl AsRef<Path>, strictness: Strictness) -> Result<Self> {
         let path = path.as_ref();
 
         debug!("Finding context specification in {}", path.display());
-        Spec::find_in_path(path)
-            .map(|m_spec| match m_spec {
-                Some(spec) => {
-                    debug!("Context found");
-                    spec
-                }
-                None => {
-                    debug!("No context found, using default.");
-                    Default::default()
-                }
-            })
-            .map(Self::new)
+        let spec = match Spec::find_in_path_checked(path, strictness)? {
+            Some(spec) => {
+                debug!("Context found");
+                spec
+            }
+            None => {
+                debug!("No context found, using default.");
+                Default::default()
+            }
+        };
+        Self::new(spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapping::LinkMode;
+
+    #[test]
+    fn test_context_from_spec() {
+        let spec = Spec::new(
+            Some(vec![Shorthand::Name("in_home".into())]),
+            Some(vec![Shorthand::Mapped(Mapping {
+                source: "in_config".into(),
+                target: Some("renamed".into()),
+                dot: None,
+                link: None,
+                mode: None,
+                permissions: None,
+            })]),
+            None,
+            None,
+        );
+        let context = SpecContext::new(spec).unwrap();
+        assert_eq!(context.targets.len(), 2);
+        assert_eq!(
+            context.targets.get("in_config"),
+            Some(&Destination::Config.locate(Target::new(
+                "renamed".into(),
+                None,
+                None,
+                LinkMode::Symlink,
+                None
+            )))
+        );
+    }
+
+    #[test]
+    fn test_duplicate_source_across_sections() {
+        let spec = Spec::new(
+            Some(vec![Shorthand::Name("both".into())]),
+            Some(vec![Shorthand::Name("both".into())]),
+            None,
+            None,
+        );
+        assert!(matches!(
+            SpecContext::new(spec),
+            Err(SpecContextError::DuplicateSource {
+                source_name,
+                first: Destination::Home,
+                second: Destination::Config,
+            }) if source_name == "both"
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_source_within_section() {
+        let spec = Spec::new(
+            Some(vec![
+                Shorthand::Name("twice".into()),
+                Shorthand::Mapped(Mapping {
+                    source: "twice".into(),
+                    target: Some("other".into()),
+                    dot: None,
+                    link: None,
+                    mode: None,
+                    permissions: None,
+                }),
+            ]),
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(
+            SpecContext::new(spec),
+            Err(SpecContextError::DuplicateSource { .. })
+        ));
     }
 }
@@ -1,33 +1,104 @@
-use std::{collections::HashSet, path::PathBuf};
+use std::{collections::HashSet, fmt, path::PathBuf};
 
 use derive_more::derive::Constructor;
-use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 
-use crate::config::file::{ConfigType, ReadFromConfig};
+use crate::{
+    config::file::{ConfigType, ReadFromConfig},
+    mapping::{LinkMode, LinkStyle, Permissions},
+};
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+// Deserialized by hand rather than with `untagged` so that unknown keys inside
+// a mapping can still be seen (and reported) by the config reader.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum Shorthand {
+    /// Name of a file in the directory, linked under the same name
     Name(String),
     Mapped(Mapping),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Mapping {
+    /// Name of a file in the directory
     pub source: String,
-    #[serde(default)]
+    /// Name to link as, defaulting to the source name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub target: Option<String>,
-    #[serde(default)]
+    /// Whether to prefix the target with a dot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub dot: Option<bool>,
+    /// How to link, overriding the style in the rc file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<LinkStyle>,
+    /// Whether to symlink, hard link or decrypt a secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<LinkMode>,
+    /// Permissions to give the source, file or directory, such as "0600"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
 }
 
 pub type Section<T> = Option<Vec<T>>;
 
-#[derive(Debug, Default, Clone, Constructor, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Constructor, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Spec {
+    /// Files linked into the home directory, dotted by default
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub home: Section<Shorthand>,
+    /// Files linked into the config directory, not dotted by default
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub config: Section<Shorthand>,
+    /// Files in the directory to skip
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ignore: Option<HashSet<String>>,
+    /// Commands to run when links in the directory change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Hooks>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct Hooks {
+    /// Run before any links in the directory are created
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_sync: Vec<String>,
+    /// Run after the links in the directory were created
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_sync: Vec<String>,
+    /// Run after each link in the directory is created
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_link: Vec<String>,
+}
+
+struct ShorthandVisitor;
+
+impl<'de> Visitor<'de> for ShorthandVisitor {
+    type Value = Shorthand;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a name or a mapping with a source")
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<Self::Value, E> {
+        Ok(Shorthand::Name(name.to_owned()))
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, map: M) -> Result<Self::Value, M::Error> {
+        Mapping::deserialize(MapAccessDeserializer::new(map)).map(Shorthand::Mapped)
+    }
+}
+
+impl<'de> Deserialize<'de> for Shorthand {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ShorthandVisitor)
+    }
 }
 
 impl From<String> for Mapping {
@@ -36,6 +107,9 @@ impl From<String> for Mapping {
             source,
             target: None,
             dot: None,
+            link: None,
+            mode: None,
+            permissions: None,
         }
     }
 }
@@ -49,6 +123,12 @@ impl From<Shorthand> for Mapping {
     }
 }
 
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_sync.is_empty() && self.post_sync.is_empty() && self.on_link.is_empty()
+    }
+}
+
 impl ReadFromConfig for Spec {
     fn config_type() -> ConfigType {
         ConfigType::default_yaml(PathBuf::from(".dot"))
@@ -58,6 +138,7 @@ impl ReadFromConfig for Spec {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::file::ConfigFormat;
     use indoc::indoc;
 
     #[test]
@@ -66,6 +147,9 @@ mod tests {
             source: "source".into(),
             target: Some("target".into()),
             dot: Some(true),
+            link: None,
+            mode: None,
+            permissions: None,
         };
         assert_eq!(mapping.clone(), Shorthand::Mapped(mapping).into());
     }
@@ -76,6 +160,9 @@ mod tests {
             source: "source".into(),
             target: None,
             dot: None,
+            link: None,
+            mode: None,
+            permissions: None,
         };
         assert_eq!(mapping, Shorthand::Name("source".into()).into());
     }
@@ -87,7 +174,7 @@ mod tests {
                 "home": ["name"]
             }
         "#};
-        let expected = Spec::new(Some(vec![Shorthand::Name("name".into())]), None, None);
+        let expected = Spec::new(Some(vec![Shorthand::Name("name".into())]), None, None, None);
         assert_eq!(expected, serde_json::from_str(doc).unwrap());
     }
 
@@ -110,12 +197,39 @@ mod tests {
                 source: "source_name".into(),
                 target: Some("target_name".into()),
                 dot: Some(true),
+                link: None,
+                mode: None,
+                permissions: None,
             })]),
             None,
+            None,
         );
         assert_eq!(expected, serde_json::from_str(doc).unwrap());
     }
 
+    #[test]
+    fn test_deserialize_permissions() {
+        let doc = indoc! {r#"
+            home:
+              - source: ssh
+                permissions: "0700"
+        "#};
+        let (spec, _): (Spec, _) = ConfigFormat::Yaml
+            .read_with_unknown(doc.as_bytes())
+            .unwrap();
+        let Some(Shorthand::Mapped(mapping)) = spec.home.unwrap().pop() else {
+            panic!("expected a mapping");
+        };
+        assert_eq!(mapping.permissions, Some(Permissions(0o700)));
+
+        for invalid in ["\"0800\"", "\"77777\"", "\"u+x\""] {
+            let doc = format!("home: [{{source: ssh, permissions: {}}}]", invalid);
+            assert!(ConfigFormat::Yaml
+                .read_with_unknown::<Spec>(doc.as_bytes())
+                .is_err());
+        }
+    }
+
     #[test]
     fn test_deserialize_ignore_section() {
         let doc = indoc! {r#"
@@ -130,6 +244,7 @@ mod tests {
             None,
             None,
             Some(["ignoreme".into(), "dontread".into()].into_iter().collect()),
+            None,
         );
         assert_eq!(expected, serde_json::from_str(doc).unwrap());
     }
@@ -162,16 +277,107 @@ mod tests {
                     source: "source_name".into(),
                     target: Some("target_name".into()),
                     dot: None,
+                    link: None,
+                    mode: None,
+                    permissions: None,
                 }),
                 Shorthand::Mapped(Mapping {
                     source: "original_name".into(),
                     target: None,
                     dot: Some(false),
+                    link: None,
+                    mode: None,
+                    permissions: None,
                 }),
             ]),
             None,
             Some(["ignoreme".into(), "dontread".into()].into_iter().collect()),
+            None,
         );
         assert_eq!(expected, serde_json::from_str(doc).unwrap());
     }
+
+    #[test]
+    fn test_deserialize_hooks() {
+        let doc = indoc! {r#"
+            home:
+              - tmux.conf
+            hooks:
+              post_sync:
+                - tmux source-file ~/.tmux.conf
+        "#};
+        let (spec, unknown): (Spec, _) = ConfigFormat::Yaml
+            .read_with_unknown(doc.as_bytes())
+            .unwrap();
+        let expected = Spec::new(
+            Some(vec![Shorthand::Name("tmux.conf".into())]),
+            None,
+            None,
+            Some(Hooks {
+                post_sync: vec!["tmux source-file ~/.tmux.conf".into()],
+                ..Default::default()
+            }),
+        );
+        assert_eq!(expected, spec);
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_keys_reported() {
+        let doc = indoc! {r#"
+            hom:
+              - misplaced
+            config:
+              - plain
+              - source: source_name
+                tagret: target_name
+        "#};
+        let (spec, unknown): (Spec, _) = ConfigFormat::Yaml
+            .read_with_unknown(doc.as_bytes())
+            .unwrap();
+        let expected = Spec::new(
+            None,
+            Some(vec![
+                Shorthand::Name("plain".into()),
+                Shorthand::Mapped(Mapping {
+                    source: "source_name".into(),
+                    target: None,
+                    dot: None,
+                    link: None,
+                    mode: None,
+                    permissions: None,
+                }),
+            ]),
+            None,
+            None,
+        );
+        assert_eq!(expected, spec);
+        assert_eq!(
+            unknown,
+            vec!["hom".to_string(), "config.1.tagret".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_no_unknown_keys() {
+        let doc = indoc! {r#"
+            {
+                "home": ["name", {"source": "source_name", "dot": false}],
+                "ignore": ["ignoreme"]
+            }
+        "#};
+        let (_, unknown): (Spec, _) = ConfigFormat::Json
+            .read_with_unknown(doc.as_bytes())
+            .unwrap();
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn test_shorthand_schema_is_name_or_mapping() {
+        let schema = serde_json::to_value(schemars::schema_for!(Shorthand)).unwrap();
+        let any_of = schema["anyOf"].as_array().unwrap();
+        assert_eq!(any_of.len(), 2);
+        assert_eq!(any_of[0]["type"], "string");
+        assert_eq!(any_of[1]["$ref"], "#/definitions/Mapping");
+    }
 }
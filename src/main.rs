@@ -7,34 +7,62 @@ mod tasks;
 mod util;
 mod validation;
 
+use std::process::ExitCode;
+
 use anyhow::Result;
+use clap::CommandFactory;
+use clap_complete::CompleteEnv;
 use components::environment::inference::DirsEnvironmentInference;
 
-use app::{cli::parse_cli, dotzo::DotzoApp, logging::setup_logging};
+use app::{
+    cli::{parse_cli, Cli},
+    dotzo::DotzoApp,
+    exit::Failure,
+    logging::setup_logging,
+};
 use tasks::run::run;
 use util::{
     actions::{DryActions, StandardActions},
     fs::StandardFsRead,
-    prompting::InquirePrompter,
+    git::{DryGit, StandardGit},
+    prompting::{InquirePrompter, ModePrompter},
 };
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
+    // Answers the shell when called back by dynamic completions
+    CompleteEnv::with_factory(Cli::command).complete();
+
     let cli = parse_cli();
-    setup_logging(cli.verbose.log_level_filter())?;
+    match try_main(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // For the log file, at debug since the console gets it below
+            log::debug!("Failed: {:#}", e);
+            eprintln!("Error: {:?}", e);
+            Failure::classify(e.chain()).into()
+        }
+    }
+}
+
+fn try_main(cli: &Cli) -> Result<()> {
+    let log_file = setup_logging(cli.verbose.log_level_filter(), cli.log_format)?;
 
     // Injectable
     let fs_read = StandardFsRead::new();
-    let prompter = InquirePrompter::new();
+    let inquire = InquirePrompter::new();
+    let prompter = ModePrompter::new(&inquire, cli.input_mode());
     let env_inference = DirsEnvironmentInference::new();
+    let git = StandardGit::new();
 
     if cli.dry_run {
         let actions = DryActions::new(&fs_read);
-        let app = DotzoApp::new_with_fs(&fs_read, &actions, &prompter, &env_inference);
-        run(&app, &cli)?;
+        let git = DryGit::new(&git);
+        let app = DotzoApp::new_with_fs(&fs_read, &actions, &prompter, &env_inference, &git);
+        run(&app, cli, &log_file)?;
     } else {
         let actions = StandardActions::new();
-        let app = DotzoApp::new_with_fs(&fs_read, &actions, &prompter, &env_inference);
-        run(&app, &cli)?;
+        let app = DotzoApp::new_with_fs(&fs_read, &actions, &prompter, &env_inference, &git);
+        run(&app, cli, &log_file)?;
     }
     Ok(())
 }
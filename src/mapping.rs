@@ -1,6 +1,9 @@
 use std::{collections::HashMap, path::PathBuf};
 
-use derive_more::derive::Constructor;
+use derive_more::derive::{Constructor, Display};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Destination {
@@ -8,10 +11,66 @@ pub enum Destination {
     Config,
 }
 
+/// How a link refers to its source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStyle {
+    /// Relative to the directory of the link, surviving moves of the whole home
+    #[default]
+    Relative,
+    /// The absolute path of the source, for when home and the repo are mounted apart
+    Absolute,
+}
+
+/// What gets put at the target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkMode {
+    /// A symlink to the source
+    #[default]
+    Symlink,
+    /// A hard link to the source, for tools that won't follow symlinks. Source and target
+    /// must be on the same filesystem
+    Hardlink,
+    /// An age encrypted source, decrypted into a private copy at the target
+    Secret,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Permissions {0:?} are not an octal mode such as \"0600\"")]
+pub struct InvalidPermissions(String);
+
+/// Permission bits written in octal, such as "0600"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+#[display("{_0:04o}")]
+pub struct Permissions(#[schemars(with = "String")] pub u32);
+
+impl TryFrom<String> for Permissions {
+    type Error = InvalidPermissions;
+
+    fn try_from(permissions: String) -> Result<Self, Self::Error> {
+        match u32::from_str_radix(&permissions, 8) {
+            Ok(mode) if mode <= 0o7777 && !permissions.starts_with('+') => Ok(Self(mode)),
+            _ => Err(InvalidPermissions(permissions)),
+        }
+    }
+}
+
+impl From<Permissions> for String {
+    fn from(permissions: Permissions) -> Self {
+        permissions.to_string()
+    }
+}
+
 #[derive(Debug, Constructor, Clone, PartialEq, Eq)]
 pub struct Target {
     pub name: String,
     pub dot: Option<bool>,
+    // Falls back to the rc file's style
+    pub link: Option<LinkStyle>,
+    pub mode: LinkMode,
+    pub permissions: Option<Permissions>,
 }
 
 #[derive(Debug, Constructor, Clone, PartialEq, Eq)]
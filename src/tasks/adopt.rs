@@ -0,0 +1,355 @@
+use std::{
+    env::current_dir,
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use thiserror::Error;
+
+use crate::{
+    app::{cli::Cli, types::App},
+    components::dotzo::types::Dotzo,
+    config::{
+        file::{ConfigFileReadError, ConfigFileWriteError, ConfigFormat, ReadFromConfig},
+        spec::types::{Mapping, Shorthand, Spec},
+    },
+    mapping::{Destination, LinkMode},
+    util::{
+        actions::{Actions, Error as ActionError},
+        fs::{normalize, LinkReader, MetadataChecks},
+        prompting::{Prompter, PrompterError},
+        secret::{Vault, VaultError},
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum AdoptTaskError {
+    #[error("No repo is configured to adopt into")]
+    NoRepo,
+
+    #[error("Encrypting needs secret_key set in the rc file")]
+    NoSecretKey,
+
+    #[error("{} is not a regular file", .0.display())]
+    NotAFile(PathBuf),
+
+    #[error("{} is not directly in home or the config directory", .0.display())]
+    NotInDestination(PathBuf),
+
+    #[error("{} already exists", .0.display())]
+    Exists(PathBuf),
+
+    #[error("Secret key error: {0}")]
+    Vault(#[from] VaultError),
+
+    #[error("Error reading spec file: {0}")]
+    SpecRead(#[from] ConfigFileReadError),
+
+    #[error("Error writing spec file: {0}")]
+    SpecWrite(#[from] ConfigFileWriteError),
+
+    #[error("Can't add to {} without rewriting it, add this to it instead:\n{}", .0.display(), .1)]
+    SpecNotAppendable(PathBuf, String),
+
+    #[error("Prompt error")]
+    Prompt(#[from] PrompterError),
+
+    #[error("Declined to adopt")]
+    Declined,
+
+    #[error("Action error: {0}")]
+    Action(#[from] ActionError),
+
+    #[error("Io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = core::result::Result<T, AdoptTaskError>;
+
+// The secret mapping for a file, named without its leading dot, which the destination adds back
+fn mapping_for(file_name: &str, dot_default: bool) -> Mapping {
+    let (name, dotted) = match file_name.strip_prefix('.') {
+        Some(name) if !name.is_empty() => (name, true),
+        _ => (file_name, false),
+    };
+    let mut mapping = Mapping::from(format!("{}.age", name));
+    mapping.target = Some(name.to_owned());
+    mapping.dot = (dotted != dot_default).then_some(dotted);
+    mapping.mode = Some(LinkMode::Secret);
+    mapping
+}
+
+fn section<'s>(spec: &'s mut Spec, destination: &Destination) -> &'s mut Option<Vec<Shorthand>> {
+    match destination {
+        Destination::Home => &mut spec.home,
+        Destination::Config => &mut spec.config,
+    }
+}
+
+fn section_key(destination: &Destination) -> &'static str {
+    match destination {
+        Destination::Home => "home",
+        Destination::Config => "config",
+    }
+}
+
+// The spec text with the mapping added to the end of its section, leaving the rest of the text
+// as it is. Only block style yaml is edited, and only when the result reads back as the spec
+// with the mapping added.
+fn append_mapping(
+    text: &str,
+    format: ConfigFormat,
+    destination: &Destination,
+    mapping: &Mapping,
+    expected: &Spec,
+) -> Option<String> {
+    if format != ConfigFormat::Yaml {
+        return None;
+    }
+    let mut added = Spec::default();
+    *section(&mut added, destination) = Some(vec![Shorthand::Mapped(mapping.clone())]);
+    let snippet = format.write(&added).ok()?;
+
+    let mut lines: Vec<&str> = text.lines().collect();
+    let key = format!("{}:", section_key(destination));
+    let appended = match lines.iter().position(|line| line.trim_end() == key) {
+        // A new section at the end of the file
+        None => {
+            let mut appended = text.trim_end().to_owned();
+            if !appended.is_empty() {
+                appended.push('\n');
+            }
+            appended + &snippet
+        }
+        Some(start) => {
+            // The section runs to the next top level line
+            let end = lines[start + 1..]
+                .iter()
+                .position(|line| !line.is_empty() && !line.starts_with([' ', '-', '#']))
+                .map_or(lines.len(), |end| start + 1 + end);
+            let last = (start + 1..end).rev().find(|&i| {
+                let line = lines[i].trim_start();
+                !line.is_empty() && !line.starts_with('#')
+            })?;
+            let indent = lines[start + 1..end]
+                .iter()
+                .find_map(|line| {
+                    line.trim_start()
+                        .starts_with("- ")
+                        .then(|| &line[..line.len() - line.trim_start().len()])
+                })
+                .unwrap_or("");
+            let items: Vec<String> = snippet
+                .lines()
+                .skip(1)
+                .map(|line| format!("{}{}", indent, line))
+                .collect();
+            lines.splice(last + 1..last + 1, items.iter().map(String::as_str));
+            lines.join("\n") + "\n"
+        }
+    };
+
+    let (read, _): (Spec, Vec<String>) = format.read_with_unknown(appended.as_bytes()).ok()?;
+    (read == *expected).then_some(appended)
+}
+
+// Stores an encrypted copy of the file as the source, leaving the file as the decrypted copy
+fn encrypt_file<LR: LinkReader, A: Actions>(
+    (link_reader, actions): (&LR, &A),
+    vault: &Vault,
+    file: &Path,
+    source: &Path,
+) -> Result<()> {
+    let encrypted = vault.encrypt(&link_reader.read_file(file)?)?;
+    actions.write_file(source, &encrypted)?;
+    // The file stays as the decrypted copy, so it must be as private as sync makes them
+    actions.set_permissions(file, 0o600)?;
+    Ok(())
+}
+
+/// Stores an encrypted copy of a file from home in the primary repo as a secret, leaving the
+/// file in place, and adds its mapping to the spec
+pub fn adopt_task<'a, APP: App<'a>>(
+    app: &'a APP,
+    cli: &Cli,
+    path: &Path,
+    into: Option<&Path>,
+    dotzo: Dotzo,
+) -> Result<()> {
+    let vault = dotzo.vault()?.ok_or(AdoptTaskError::NoSecretKey)?;
+    let environment = &dotzo.environment;
+
+    let file = normalize(&current_dir()?.join(path));
+    if !app.metadata_checks().is_file(&file) || app.metadata_checks().is_symlink(&file) {
+        return Err(AdoptTaskError::NotAFile(file));
+    }
+    let parent = file.parent().unwrap_or(&file);
+    let destination = [Destination::Home, Destination::Config]
+        .into_iter()
+        .find(|destination| environment.destination_data(destination).path == parent)
+        .ok_or_else(|| AdoptTaskError::NotInDestination(file.clone()))?;
+    let file_name = file
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let mapping = mapping_for(
+        &file_name,
+        environment.destination_data(&destination).dot_default,
+    );
+
+    let repo = dotzo.repos.first().ok_or(AdoptTaskError::NoRepo)?;
+    let target_name = mapping.target.clone().unwrap_or(mapping.source.clone());
+    let dir = repo
+        .etc()
+        .join(into.map_or_else(|| PathBuf::from(&target_name), ToOwned::to_owned));
+    let source = dir.join(&mapping.source);
+    if app.metadata_checks().exists(&source) || app.metadata_checks().is_symlink(&source) {
+        return Err(AdoptTaskError::Exists(source));
+    }
+
+    let (spec_path, contents) = match Spec::config_type().find_config_file(&dir)? {
+        Some(spec_file) => {
+            let mut spec: Spec = spec_file.read_config_checked(cli.spec_strictness())?;
+            section(&mut spec, &destination)
+                .get_or_insert_with(Vec::new)
+                .push(Shorthand::Mapped(mapping.clone()));
+            let text = String::from_utf8_lossy(&app.link_reader().read_file(spec_file.path())?)
+                .into_owned();
+            let contents = append_mapping(&text, spec_file.format(), &destination, &mapping, &spec);
+            let contents = contents.ok_or_else(|| {
+                let mut added = Spec::default();
+                *section(&mut added, &destination) = Some(vec![Shorthand::Mapped(mapping)]);
+                AdoptTaskError::SpecNotAppendable(
+                    spec_file.path().to_owned(),
+                    ConfigFormat::Yaml.write(&added).unwrap_or_default(),
+                )
+            })?;
+            (spec_file.path().to_owned(), contents)
+        }
+        None => {
+            let mut spec = Spec::default();
+            *section(&mut spec, &destination) = Some(vec![Shorthand::Mapped(mapping)]);
+            (dir.join(".dot"), ConfigFormat::Yaml.write(&spec)?)
+        }
+    };
+
+    if !app.prompter().confirm(
+        format!(
+            "Encrypt {} into {} as a secret",
+            file.display(),
+            source.display()
+        ),
+        true,
+    )? {
+        return Err(AdoptTaskError::Declined);
+    }
+
+    if !app.metadata_checks().is_dir(&dir) {
+        app.actions().make_dir(&dir)?;
+    }
+    encrypt_file((app.link_reader(), app.actions()), &vault, &file, &source)?;
+    app.actions().write_file(&spec_path, &contents)?;
+    info!("Added the mapping to {}", spec_path.display());
+
+    println!("Adopted {}", file.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use age::x25519::Identity;
+    use indoc::indoc;
+
+    use super::*;
+    use crate::util::{
+        actions::testing::TestActions,
+        fs::testing::{TestFile, TestFs},
+    };
+
+    fn test_fs() -> TestFs {
+        let mut fs = TestFs::new([(PathBuf::from("/home/.netrc"), TestFile::Regular)]);
+        fs.add_directory("/home/_/etc/netrc");
+        fs.contents
+            .insert(PathBuf::from("/home/.netrc"), b"machine".to_vec());
+        fs
+    }
+
+    #[test]
+    fn test_encrypt_file() {
+        let vault = Vault::new(Identity::generate());
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+
+        encrypt_file(
+            (&fs, &actions),
+            &vault,
+            Path::new("/home/.netrc"),
+            Path::new("/home/_/etc/netrc/netrc.age"),
+        )
+        .unwrap();
+
+        let fs = actions.fs.borrow();
+        let encrypted = fs.read_file("/home/_/etc/netrc/netrc.age").unwrap();
+        assert_eq!(vault.decrypt(&encrypted).unwrap(), b"machine");
+        assert!(fs.is_file("/home/.netrc"));
+        assert_eq!(fs.mode("/home/.netrc").unwrap(), 0o600);
+    }
+
+    fn appended(text: &str, destination: Destination) -> Option<String> {
+        let mapping = mapping_for(".netrc", true);
+        let (mut expected, _): (Spec, Vec<String>) = ConfigFormat::Yaml
+            .read_with_unknown(text.as_bytes())
+            .unwrap();
+        section(&mut expected, &destination)
+            .get_or_insert_with(Vec::new)
+            .push(Shorthand::Mapped(mapping.clone()));
+        append_mapping(text, ConfigFormat::Yaml, &destination, &mapping, &expected)
+    }
+
+    #[test]
+    fn test_append_keeps_comments() {
+        let text = indoc! {"
+            # Shell files
+            home:
+              - bashrc # login shells too
+              - source: profile
+                dot: true
+
+            ignore:
+              - README.md
+        "};
+        assert_eq!(
+            appended(text, Destination::Home).unwrap(),
+            indoc! {"
+                # Shell files
+                home:
+                  - bashrc # login shells too
+                  - source: profile
+                    dot: true
+                  - source: netrc.age
+                    target: netrc
+                    mode: secret
+
+                ignore:
+                  - README.md
+            "}
+        );
+    }
+
+    #[test]
+    fn test_append_new_section() {
+        let text = "# Editor\nhome:\n- vimrc\n";
+        assert_eq!(
+            appended(text, Destination::Config).unwrap(),
+            "# Editor\nhome:\n- vimrc\nconfig:\n- source: netrc.age\n  target: netrc\n  mode: secret\n"
+        );
+    }
+
+    #[test]
+    fn test_append_refuses_flow_style() {
+        assert_eq!(appended("home: [vimrc]\n", Destination::Home), None);
+    }
+}
@@ -0,0 +1,114 @@
+use log::info;
+use thiserror::Error;
+
+use crate::{
+    app::{cli::Cli, types::App},
+    components::{
+        dotzo::types::Dotzo,
+        repo::checks::{
+            spec::{SpecCheck, SpecCheckError},
+            structure::StructureCheckError as RepoStructureCheckError,
+        },
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum CheckSpecTaskError {
+    #[error("Structure check failure: {0}")]
+    RepoStructure(#[from] RepoStructureCheckError),
+
+    #[error("Spec check failure: {0}")]
+    SpecCheck(#[from] SpecCheckError),
+
+    #[error("Found {0} problems in repo specs")]
+    Problems(usize),
+}
+
+pub type Result<T> = core::result::Result<T, CheckSpecTaskError>;
+
+pub fn check_spec_task<'a, APP: App<'a>>(app: &'a APP, cli: &Cli, dotzo: Dotzo) -> Result<()> {
+    let checker = SpecCheck::new(
+        app.metadata_checks(),
+        app.directory_listing(),
+        cli.spec_strictness(),
+    );
+
+    let mut problems = Vec::new();
+    for repo in &dotzo.repos {
+        info!("Checking the repository {}", repo.path.display());
+        app.repo_structure_check().check(repo)?;
+
+        info!("Checking specs under {}", repo.etc().display());
+        problems.extend(checker.check(repo.etc())?);
+    }
+
+    if problems.is_empty() {
+        println!("All specs are valid");
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}: {}", problem.directory.display(), problem.kind);
+    }
+    Err(CheckSpecTaskError::Problems(problems.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, fs::create_dir};
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::{
+        app::dotzo::DotzoApp,
+        components::{
+            environment::{inference::DirsEnvironmentInference, types::Environment},
+            repo::types::Repo,
+        },
+        config::rc::types::Rc,
+        util::{
+            actions::testing::TestActions,
+            fs::{
+                testing::{ScratchDir, TestFs},
+                StandardFsRead,
+            },
+            git::standard::StandardGit,
+            prompting::InquirePrompter,
+        },
+    };
+
+    #[test]
+    fn test_check_spec_changes_nothing() {
+        let root = ScratchDir::new("check-spec");
+        create_dir(root.join("etc")).unwrap();
+        std::fs::write(root.join("etc/.dot"), "home:\n- vimrc\ntagret: vim\n").unwrap();
+
+        let fs = StandardFsRead::new();
+        let actions = TestActions::new(RefCell::new(TestFs::default()));
+        let prompter = InquirePrompter::new();
+        let inference = DirsEnvironmentInference::new();
+        let git = StandardGit::new();
+        let app = DotzoApp::new_with_fs(&fs, &actions, &prompter, &inference, &git);
+        let cli = Cli::parse_from(["dotzo", "check-spec"]);
+        let environment = Environment::new(
+            root.join("home").into(),
+            root.join("home/.config").into(),
+            root.join("home/.local/share").into(),
+            root.join("home/.local/state").into(),
+            root.join("home/.cache").into(),
+        );
+        let dotzo = Dotzo::new(
+            environment,
+            vec![Repo::new(root.to_path_buf(), None)],
+            Rc::default(),
+        );
+
+        assert!(matches!(
+            check_spec_task(&app, &cli, dotzo),
+            Err(CheckSpecTaskError::Problems(1))
+        ));
+        assert_eq!(*actions.fs.borrow(), TestFs::default());
+        assert!(!root.join("home").exists());
+    }
+}
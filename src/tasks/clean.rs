@@ -0,0 +1,67 @@
+use log::info;
+use thiserror::Error;
+
+use crate::{
+    app::types::App,
+    components::{
+        dotzo::types::Dotzo,
+        linker::dangling::{DanglingLink, DanglingScan},
+    },
+    util::{
+        actions::{Actions, Error as ActionError},
+        prompting::{Prompter, PrompterError},
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum CleanTaskError {
+    #[error("Prompt error")]
+    Prompt(#[from] PrompterError),
+
+    #[error("Action error: {0}")]
+    Action(#[from] ActionError),
+
+    #[error("Declined to remove dangling links")]
+    Declined,
+}
+
+pub type Result<T> = core::result::Result<T, CleanTaskError>;
+
+/// Dangling links into the repos, left behind when their source was renamed or removed
+pub fn repo_dangling_links<'a, APP: App<'a>>(app: &'a APP, dotzo: &Dotzo) -> Vec<DanglingLink> {
+    DanglingScan::new(
+        app.metadata_checks(),
+        app.link_reader(),
+        app.directory_listing(),
+        dotzo.rc.scan_depth,
+    )
+    .scan(&dotzo.environment, &dotzo.repos)
+    .into_iter()
+    .filter(|dangling| dangling.repo.is_some())
+    .collect()
+}
+
+/// Removes the dangling links into the repos, touching nothing that is not a symlink
+pub fn clean_task<'a, APP: App<'a>>(app: &'a APP, dotzo: Dotzo) -> Result<()> {
+    let dangling = repo_dangling_links(app, &dotzo);
+    if dangling.is_empty() {
+        println!("No dangling links");
+        return Ok(());
+    }
+
+    for DanglingLink { path, linked, .. } in &dangling {
+        println!("{} -> {}", path.display(), linked.display());
+    }
+    if !app
+        .prompter()
+        .confirm(format!("Remove {} dangling links", dangling.len()), false)?
+    {
+        return Err(CleanTaskError::Declined);
+    }
+
+    for DanglingLink { path, .. } in &dangling {
+        app.actions().remove_link(path)?;
+        info!(path:% = path.display(); "Removed {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use std::io::{stdout, Write};
+
+use clap::CommandFactory;
+use clap_complete::{
+    env::{self, EnvCompleter},
+    generate, shells,
+};
+use thiserror::Error;
+
+use crate::{app::cli::Cli, util::shell::Shell};
+
+#[derive(Debug, Error)]
+pub enum CompletionsTaskError {
+    #[error("Error writing completions: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = core::result::Result<T, CompletionsTaskError>;
+
+/// Prints a completion script. Static scripts only know the commands and flags, while the
+/// dynamic one calls back into dotzo, so it can also complete paths in the repos.
+pub fn completions_task(shell: Shell, dynamic: bool) -> Result<()> {
+    let mut command = Cli::command();
+    let name = env!("CARGO_BIN_NAME");
+    let mut out = stdout();
+
+    if dynamic {
+        let completer: &dyn EnvCompleter = match shell {
+            Shell::Bash => &env::Bash,
+            Shell::Zsh => &env::Zsh,
+            Shell::Fish => &env::Fish,
+        };
+        completer.write_registration("COMPLETE", name, name, name, &mut out)?;
+    } else {
+        match shell {
+            Shell::Bash => generate(shells::Bash, &mut command, name, &mut out),
+            Shell::Zsh => generate(shells::Zsh, &mut command, name, &mut out),
+            Shell::Fish => generate(shells::Fish, &mut command, name, &mut out),
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
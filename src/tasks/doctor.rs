@@ -0,0 +1,138 @@
+use log::debug;
+use thiserror::Error;
+
+use crate::{
+    app::{cli::Cli, types::App},
+    components::{
+        doctor::{
+            checks::{CoreCheck, FilesystemCheck, LinkCheck, SshCheck, WritableCheck},
+            types::{DoctorCheck, Finding, Remedy, Severity},
+        },
+        environment::inference::EnvironmentInference,
+    },
+    tasks::{
+        init::{load_unchecked_task, InitTaskError},
+        run::lock_environment,
+    },
+    util::{actions::Actions, error::reason, lock::LockError},
+};
+
+#[derive(Debug, Error)]
+pub enum DoctorTaskError {
+    #[error("Found {0} problems in the environment")]
+    Problems(usize),
+
+    #[error("Lock error: {0}")]
+    Lock(#[from] LockError),
+}
+
+pub type Result<T> = core::result::Result<T, DoctorTaskError>;
+
+// Applies a remedy that is safe to apply, returning whether it was
+fn apply<A: Actions>(actions: &A, remedy: &Remedy) -> bool {
+    let applied = match remedy {
+        Remedy::RemoveLink(path) => actions.remove_link(path),
+        Remedy::Suggest(_) => return false,
+    };
+    match applied {
+        Ok(()) => {
+            println!("{:<8} fixed: {}", "", remedy);
+            true
+        }
+        Err(e) => {
+            println!("{:<8} could not {}: {}", "", remedy, reason(&e));
+            false
+        }
+    }
+}
+
+// No check can run without the environment, so what stopped loading it is the finding, after
+// the home check since a bad home is the usual cause
+fn load_findings<'a, APP: App<'a>>(app: &'a APP, cli: &Cli, error: InitTaskError) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if let Ok(home) = app.inference().create_home(cli.home_dir.clone()) {
+        if let Err(e) = app.home_check().check(&home) {
+            findings.push(Finding::new(Severity::Error, reason(&e), None));
+        }
+    }
+    findings.push(Finding::new(Severity::Error, reason(&error), None));
+    findings
+}
+
+/// Runs every health check, fixing the safe findings if asked to. A home that fails its check
+/// is reported as a finding rather than stopping doctor
+pub fn doctor_task<'a, APP: App<'a>>(app: &'a APP, cli: &Cli, fix: bool) -> Result<()> {
+    let checks: Vec<Box<dyn DoctorCheck + 'a>> = vec![
+        Box::new(CoreCheck::new(
+            app.home_check(),
+            app.layout_check(false, false, None),
+            app.structure_check(),
+            app.repo_structure_check(),
+        )),
+        Box::new(LinkCheck::new(
+            app.metadata_checks(),
+            app.link_reader(),
+            app.directory_listing(),
+        )),
+        Box::new(SshCheck::new(
+            app.metadata_checks(),
+            app.directory_listing(),
+        )),
+        Box::new(FilesystemCheck::new(app.metadata_checks())),
+        Box::new(WritableCheck::new(app.metadata_checks())),
+    ];
+
+    // Fixing changes the environment, so other runs are locked out until doctor is done. Nothing
+    // found without the environment can be fixed
+    let (findings, _lock) = match load_unchecked_task(app, cli) {
+        Ok(dotzo) => {
+            let lock = match fix {
+                true => lock_environment(cli, &dotzo)?,
+                false => None,
+            };
+            let findings = checks
+                .iter()
+                .flat_map(|check| {
+                    debug!("Running the {} check", check.name());
+                    check.run(&dotzo)
+                })
+                .collect();
+            (findings, lock)
+        }
+        Err(e) => (load_findings(app, cli, e), None),
+    };
+
+    let mut errors = 0;
+    let mut warnings = 0;
+    for finding in findings {
+        println!("{:<8} {}", finding.severity.to_string(), finding.message);
+        let fixed = match &finding.remedy {
+            Some(remedy) if fix && remedy.is_automatic() => apply(app.actions(), remedy),
+            Some(remedy) if remedy.is_automatic() => {
+                println!("{:<8} fix: {} (--fix does this)", "", remedy);
+                false
+            }
+            Some(remedy) => {
+                println!("{:<8} fix: {}", "", remedy);
+                false
+            }
+            None => false,
+        };
+        match finding.severity {
+            _ if fixed => (),
+            Severity::Error => errors += 1,
+            Severity::Warning => warnings += 1,
+            Severity::Info => (),
+        }
+    }
+
+    if errors == 0 && warnings == 0 {
+        println!("No problems found");
+    } else {
+        println!("{} errors, {} warnings", errors, warnings);
+    }
+    match errors {
+        0 => Ok(()),
+        errors => Err(DoctorTaskError::Problems(errors)),
+    }
+}
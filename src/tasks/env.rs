@@ -0,0 +1,30 @@
+use crate::{
+    app::types::App,
+    components::dotzo::types::Dotzo,
+    util::{fs::MetadataChecks, shell::Shell},
+};
+
+/// Prints the environment dotzo inferred as shell exports, for `eval "$(dotzo env)"`
+pub fn env_task<'a, APP: App<'a>>(app: &'a APP, shell: Option<Shell>, dotzo: Dotzo) {
+    let shell = shell.unwrap_or_else(Shell::from_env);
+    let Dotzo {
+        environment, repos, ..
+    } = dotzo;
+
+    println!("{}", shell.export("XDG_CONFIG_HOME", &environment.config));
+    println!("{}", shell.export("XDG_DATA_HOME", &environment.data));
+    println!("{}", shell.export("XDG_STATE_HOME", &environment.state));
+    println!("{}", shell.export("XDG_CACHE_HOME", &environment.cache));
+    if let Some(repo) = repos.first() {
+        println!("{}", shell.export("DOTZO_REPO", &repo.path));
+    }
+
+    let bins: Vec<_> = repos
+        .iter()
+        .map(|repo| repo.bin())
+        .filter(|bin| app.metadata_checks().is_dir(bin))
+        .collect();
+    if !bins.is_empty() {
+        println!("{}", shell.prepend_path(bins));
+    }
+}
@@ -1,4 +1,8 @@
-use crate::components::environment::types::Environment;
+use crate::{
+    app::types::App,
+    components::{dotzo::types::Dotzo, repo::types::Repo},
+    util::git::Git,
+};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -6,7 +10,17 @@ pub enum InfoTaskError {}
 
 pub type Result<T> = core::result::Result<T, InfoTaskError>;
 
-pub fn info_task(environment: Environment) -> Result<()> {
-    println!("{:#?}", environment);
+pub fn print_repo_states<G: Git>(git: &G, repos: &[Repo]) {
+    for repo in repos {
+        match repo.state(git) {
+            Ok(state) => println!("Repo {}: {}", repo.path.display(), state),
+            Err(e) => println!("Repo {}: no git state ({})", repo.path.display(), e),
+        }
+    }
+}
+
+pub fn info_task<'a, APP: App<'a>>(app: &'a APP, dotzo: Dotzo) -> Result<()> {
+    println!("{:#?}", dotzo.environment);
+    print_repo_states(app.git(), &dotzo.repos);
     Ok(())
 }
@@ -1,7 +1,8 @@
-use log::{error, info};
+use log::info;
 use thiserror::Error;
 
 use crate::{
+    action::repo_cloner::RepoClonerError,
     app::{cli::Cli, types::App},
     components::{
         dotzo::types::Dotzo,
@@ -23,34 +24,77 @@ pub enum InitTaskError {
 
     #[error("Environment inference failure: {0}")]
     EnvironmentInference(#[from] EnvironmentInferenceError),
+
+    #[error("No repo is configured")]
+    NoRepo,
+
+    #[error("Repo clone failure: {0}")]
+    Clone(#[from] RepoClonerError),
 }
 
 pub type Result<T> = core::result::Result<T, InitTaskError>;
 
-pub fn init_task<'a, APP: App<'a>>(app: &'a APP, cli: &Cli) -> Result<Dotzo> {
+/// Determines the environment and repo without changing anything on disk
+pub fn load_task<'a, APP: App<'a>>(app: &'a APP, cli: &Cli) -> Result<Dotzo> {
+    load(app, cli, true)
+}
+
+/// Like load_task, but leaves checking the home directory to the caller
+pub fn load_unchecked_task<'a, APP: App<'a>>(app: &'a APP, cli: &Cli) -> Result<Dotzo> {
+    load(app, cli, false)
+}
+
+fn load<'a, APP: App<'a>>(app: &'a APP, cli: &Cli, check_home: bool) -> Result<Dotzo> {
     let inference = app.inference();
-    let home_check = app.home_check();
-    let checks = app.layout_check(false, true);
+
+    info!("Initializing Dotzo");
 
     // Getting home
     info!("Identifying home directory");
     let home = inference.create_home(cli.home_dir.clone())?;
 
-    info!("Validating home directory");
-    home_check.check(&home)?;
+    if check_home {
+        info!("Validating home directory");
+        app.home_check().check(&home)?;
+    }
 
     info!("Loading dotzo rc file");
-    let rc = app.inference().load_rc(&home)?;
+    let rc = app.inference().load_rc(&home, cli.config.clone())?;
 
     info!("Determining the home environment");
     let environment = app.inference().create(home, &rc, cli.config_dir.clone())?;
 
-    info!("Determining the repo");
-    let repo = Repo::from_config(&environment, &rc, cli.config.clone());
+    info!("Determining the repos");
+    let repos = Repo::all_from_config(&environment, &rc, cli.repo.clone());
+    if repos.is_empty() {
+        return Err(InitTaskError::NoRepo);
+    }
+
+    Ok(Dotzo {
+        environment,
+        repos,
+        rc,
+    })
+}
+
+/// Creates what is missing from the home layout
+pub fn init_task<'a, APP: App<'a>>(app: &'a APP, cli: &Cli, dotzo: Dotzo) -> Result<Dotzo> {
+    let checks = app.layout_check(cli.yes, true, dotzo.rc.directory_permissions);
 
     info!("Checking home structure");
-    checks.check(&environment)?;
+    checks.check(&dotzo.environment)?;
     info!("Home structure checked");
 
-    Ok(Dotzo { environment, repo })
+    Ok(dotzo)
+}
+
+/// Offers to clone missing repos. Only init does, so no other command starts a clone
+pub fn clone_task<'a, APP: App<'a>>(app: &'a APP, cli: &Cli, dotzo: &Dotzo) -> Result<()> {
+    let cloner = app.repo_cloner();
+
+    info!("Checking for missing repos");
+    for repo in &dotzo.repos {
+        cloner.clone_if_missing(repo, cli.yes)?;
+    }
+    Ok(())
 }
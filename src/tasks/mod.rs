@@ -1,4 +1,16 @@
+pub mod adopt;
+pub mod check_spec;
+pub mod clean;
+pub mod completions;
+pub mod doctor;
+pub mod env;
 pub mod info;
 pub mod init;
+pub mod pull;
+pub mod push;
+pub mod relocate;
 pub mod run;
+pub mod schema;
+pub mod status;
 pub mod sync;
+pub mod watch;
@@ -0,0 +1,54 @@
+use log::info;
+use thiserror::Error;
+
+use crate::{
+    app::{cli::Cli, types::App},
+    components::{
+        dotzo::types::Dotzo,
+        repo::checks::structure::StructureCheckError as RepoStructureCheckError,
+    },
+    util::{
+        git::{Error as GitError, Git},
+        prompting::{Prompter, PrompterError},
+    },
+};
+
+use super::sync::{sync_task, SyncTaskError};
+
+#[derive(Debug, Error)]
+pub enum PullTaskError {
+    #[error("Prompt error")]
+    Prompt(#[from] PrompterError),
+
+    #[error("Structure check failure: {0}")]
+    RepoStructure(#[from] RepoStructureCheckError),
+
+    #[error("Git error: {0}")]
+    Git(#[from] GitError),
+
+    #[error("Sync after pull failed: {0}")]
+    Sync(#[from] SyncTaskError),
+}
+
+pub type Result<T> = core::result::Result<T, PullTaskError>;
+
+pub fn pull_task<'a, APP: App<'a>>(app: &'a APP, cli: &Cli, dotzo: Dotzo) -> Result<()> {
+    let git = app.git();
+    let prompting = app.prompter();
+    let repo_checks = app.repo_structure_check();
+
+    for repo in &dotzo.repos {
+        repo_checks.check(repo)?;
+        if prompting.confirm(
+            format!("Pull the latest changes into {}?", repo.path.display()),
+            true,
+        )? {
+            git.pull(&repo.path)?;
+        } else {
+            info!("Not pulling into {}", repo.path.display());
+        }
+    }
+
+    info!("Syncing after pull");
+    Ok(sync_task(app, cli, &[], dotzo)?)
+}
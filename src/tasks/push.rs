@@ -0,0 +1,72 @@
+use log::info;
+use thiserror::Error;
+
+use crate::{
+    app::types::App,
+    components::{
+        dotzo::types::Dotzo,
+        repo::checks::structure::StructureCheckError as RepoStructureCheckError,
+    },
+    util::{
+        git::{Error as GitError, Git},
+        prompting::{Prompter, PrompterError},
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum PushTaskError {
+    #[error("Prompt error")]
+    Prompt(#[from] PrompterError),
+
+    #[error("Structure check failure: {0}")]
+    RepoStructure(#[from] RepoStructureCheckError),
+
+    #[error("Git error: {0}")]
+    Git(#[from] GitError),
+
+    #[error("The working tree of {0} has conflicts")]
+    Conflicts(String),
+}
+
+pub type Result<T> = core::result::Result<T, PushTaskError>;
+
+pub fn push_task<'a, APP: App<'a>>(app: &'a APP, message: &str, dotzo: Dotzo) -> Result<()> {
+    let git = app.git();
+    let prompting = app.prompter();
+    let repo_checks = app.repo_structure_check();
+
+    for repo in &dotzo.repos {
+        repo_checks.check(repo)?;
+
+        let status = git.status(&repo.path)?;
+        if status.iter().any(|entry| entry.is_conflict()) {
+            return Err(PushTaskError::Conflicts(repo.path.display().to_string()));
+        }
+
+        let changes = repo.etc_changes(git, status)?;
+        if changes.is_empty() {
+            info!("No changes to push in {}", repo.path.display());
+            continue;
+        }
+
+        println!("Changes in {}:", repo.path.display());
+        for entry in &changes {
+            println!("  {} {}", entry.code, entry.path.display());
+        }
+
+        if prompting.confirm(
+            format!(
+                "Commit and push {} changes in {}?",
+                changes.len(),
+                repo.path.display()
+            ),
+            false,
+        )? {
+            git.commit(&repo.path, "etc", message)?;
+            git.push(&repo.path)?;
+        } else {
+            info!("Not pushing {}", repo.path.display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,434 @@
+use std::{
+    env::current_dir,
+    path::{Path, PathBuf},
+};
+
+use log::{error, info};
+use relative_path::RelativePathBuf;
+use thiserror::Error;
+
+use crate::{
+    action::make_link::{LinkChange, LinkCreator, LinkCreatorError},
+    app::{cli::Cli, types::App},
+    components::{
+        dotzo::types::Dotzo,
+        environment::types::Environment,
+        linker::{
+            link::{DotLinker, DotLinkerError},
+            report::{LinkOutcome, SyncReport},
+        },
+        repo::{
+            checks::structure::StructureCheckError as RepoStructureCheckError,
+            tree::{TreeTraverser, TreeTraverserError},
+            types::Repo,
+        },
+    },
+    config::{
+        file::{ConfigFileReadError, ConfigFileWriteError, ConfigFormat, ReadFromConfig},
+        rc::types::Rc,
+    },
+    mapping::DotMap,
+    util::{
+        actions::{Actions, Error as ActionError},
+        error::reason,
+        fs::{normalize, LinkReader, MetadataChecks},
+        prompting::{Prompter, PrompterError},
+        secret::VaultError,
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum RelocateTaskError {
+    #[error("No primary repo is configured in the rc file")]
+    NoRepo,
+
+    #[error("Can't find the rc file to update")]
+    RcNotFound,
+
+    #[error("{} is not inside home", .0.display())]
+    OutsideHome(PathBuf),
+
+    #[error("{} already exists", .0.display())]
+    Exists(PathBuf),
+
+    #[error("Structure check failure: {0}")]
+    RepoStructure(#[from] RepoStructureCheckError),
+
+    #[error("Error traversing repo: {0}")]
+    Traversal(#[from] TreeTraverserError),
+
+    #[error("Link error: {0}")]
+    Link(#[from] DotLinkerError),
+
+    #[error("Link creation error: {0}")]
+    LinkCreation(#[from] LinkCreatorError),
+
+    #[error("Error reading rc file: {0}")]
+    RcRead(#[from] ConfigFileReadError),
+
+    #[error("Error writing rc file: {0}")]
+    RcWrite(#[from] ConfigFileWriteError),
+
+    #[error("Prompt error")]
+    Prompt(#[from] PrompterError),
+
+    #[error("Declined to relocate")]
+    Declined,
+
+    #[error("Action error: {0}")]
+    Action(#[from] ActionError),
+
+    #[error("{failed} of {total} links could not be rewritten")]
+    PartialFailure { failed: usize, total: usize },
+
+    #[error("Secret key error: {0}")]
+    Vault(#[from] VaultError),
+
+    #[error("Io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = core::result::Result<T, RelocateTaskError>;
+
+// The mappings from the old location whose links lead to their source, which are the ones to
+// rewrite whatever else is off about them
+fn links_in_place<LR: LinkReader>(
+    link_reader: &LR,
+    environment: &Environment,
+    dot_maps: impl IntoIterator<Item = DotMap>,
+    old: &Path,
+) -> Vec<DotMap> {
+    let mut in_place: Vec<DotMap> = dot_maps
+        .into_iter()
+        .filter(|dot_map| dot_map.source.starts_with(old))
+        .filter(|dot_map| {
+            let target = environment.target_path(&dot_map.target);
+            let resolved = link_reader
+                .read_link(&target)
+                .map(|linked| normalize(&target.parent().unwrap_or(Path::new("/")).join(linked)));
+            resolved.is_ok_and(|resolved| resolved == dot_map.source)
+        })
+        .collect();
+    in_place.sort_by(|a, b| a.source.cmp(&b.source));
+    in_place
+}
+
+// The rc file pointing the primary repo at its new location
+fn relocated_rc(mut rc: Rc, location: RelativePathBuf, format: ConfigFormat) -> Result<String> {
+    if let Some(repo) = rc.repo.as_mut() {
+        repo.location = location;
+    }
+    Ok(format.write(&rc)?)
+}
+
+// Writes the rc file first, so a failed write leaves the repo where the rc file says it is,
+// and puts the old rc file back if the move fails
+fn move_repo<MC: MetadataChecks, A: Actions>(
+    metadata_checks: &MC,
+    actions: &A,
+    (old, new): (&Path, &Path),
+    rc_file: &Path,
+    (old_rc, new_rc): (&str, &str),
+) -> Result<()> {
+    if let Some(parent) = new.parent() {
+        if !metadata_checks.is_dir(parent) {
+            actions.make_dir(parent)?;
+        }
+    }
+    actions.write_file(rc_file, new_rc)?;
+    if let Err(e) = actions.rename(old, new) {
+        actions.write_file(rc_file, old_rc)?;
+        return Err(e.into());
+    }
+    info!("Updated the repo location in {}", rc_file.display());
+    Ok(())
+}
+
+// Points the links at the new location, carrying on past failures since the repo has
+// already moved
+fn rewrite_links<MC: MetadataChecks, LR: LinkReader, A: Actions>(
+    linker: &DotLinker<MC, LR>,
+    link_creator: &LinkCreator<MC, LR, A>,
+    environment: &Environment,
+    in_place: Vec<DotMap>,
+    (old, new): (&Path, &Path),
+) -> SyncReport {
+    let mut report = SyncReport::default();
+    for DotMap { source, target } in in_place {
+        let source = new.join(source.strip_prefix(old).unwrap_or(&source));
+        let target_path = environment.target_path(&target);
+        let rewritten = linker
+            .create_link(environment, &DotMap::new(source, target))
+            .map_err(RelocateTaskError::from)
+            .and_then(|link| Ok(link_creator.apply(LinkChange::Fix, &link)?));
+        match rewritten {
+            Ok(_) => report.record(&target_path, LinkOutcome::Applied(LinkChange::Fix)),
+            Err(e) => {
+                error!(
+                    "Failed to rewrite {}: {}",
+                    target_path.display(),
+                    reason(&e)
+                );
+                report.record(
+                    &target_path,
+                    LinkOutcome::Failed(LinkChange::Fix, reason(&e)),
+                );
+            }
+        }
+    }
+    report
+}
+
+/// Moves the primary repo, points the rc file at its new location and rewrites every link
+/// that was in place
+pub fn relocate_task<'a, APP: App<'a>>(
+    app: &'a APP,
+    cli: &Cli,
+    new_path: &Path,
+    dotzo: Dotzo,
+) -> Result<()> {
+    let vault = dotzo.vault()?;
+    let linker = DotLinker::new(
+        app.metadata_checks(),
+        app.link_reader(),
+        dotzo.rc.link,
+        vault.as_ref(),
+    );
+    let link_creator = LinkCreator::new(
+        app.metadata_checks(),
+        app.link_reader(),
+        app.actions(),
+        vault.as_ref(),
+    );
+    let traverser = TreeTraverser::new(
+        app.metadata_checks(),
+        app.directory_listing(),
+        cli.spec_strictness(),
+    );
+    let Dotzo {
+        environment,
+        repos,
+        rc,
+    } = dotzo;
+    let home = environment.home.as_ref();
+
+    // The primary repo, where --repo may have put it
+    let old = match (&rc.repo, repos.first()) {
+        (Some(_), Some(repo)) => repo.path.clone(),
+        _ => return Err(RelocateTaskError::NoRepo),
+    };
+    let new = normalize(&current_dir()?.join(new_path));
+    let location = new
+        .strip_prefix(home)
+        .ok()
+        .and_then(|relative| RelativePathBuf::from_path(relative).ok())
+        .ok_or_else(|| RelocateTaskError::OutsideHome(new.clone()))?;
+    if app.metadata_checks().exists(&new) || app.metadata_checks().is_symlink(&new) {
+        return Err(RelocateTaskError::Exists(new));
+    }
+    app.repo_structure_check()
+        .check(&Repo::new(old.clone(), None))?;
+
+    info!("Finding the links in place for {}", old.display());
+    let dot_maps = traverser
+        .traverse_layered(&environment, &repos)?
+        .dot_maps
+        .into_values()
+        .map(|layered| layered.dot_map);
+    let in_place = links_in_place(app.link_reader(), &environment, dot_maps, &old);
+
+    // The rc file that was loaded, which --config may have given
+    let rc_file = match &cli.config {
+        Some(path) => Rc::config_type().get_config_file(path)?,
+        None => Rc::config_type().find_config_file(home)?,
+    }
+    .ok_or(RelocateTaskError::RcNotFound)?;
+    let old_rc =
+        String::from_utf8_lossy(&app.link_reader().read_file(rc_file.path())?).into_owned();
+    let new_rc = relocated_rc(rc, location, rc_file.format())?;
+    // The rc file is written out from its settings, so anything else in it is lost
+    if !app.prompter().confirm(
+        format!(
+            "Move {} to {}, rewrite {} links and rewrite {} without its comments",
+            old.display(),
+            new.display(),
+            in_place.len(),
+            rc_file.path().display()
+        ),
+        false,
+    )? {
+        return Err(RelocateTaskError::Declined);
+    }
+
+    move_repo(
+        app.metadata_checks(),
+        app.actions(),
+        (&old, &new),
+        rc_file.path(),
+        (&old_rc, &new_rc),
+    )?;
+    let report = rewrite_links(&linker, &link_creator, &environment, in_place, (&old, &new));
+
+    for line in report.lines() {
+        println!("{}", line);
+    }
+    match report.failures() {
+        0 => Ok(()),
+        failed => Err(RelocateTaskError::PartialFailure {
+            failed,
+            total: report.outcomes.len(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::{
+        mapping::{Destination, LinkMode, LinkStyle, Target},
+        util::{
+            actions::testing::TestActions,
+            fs::testing::{TestFile, TestFs},
+        },
+    };
+
+    const RC_FILE: &str = "/home/.dotrc.yaml";
+
+    fn environment() -> Environment {
+        Environment::new(
+            PathBuf::from("/home").into(),
+            PathBuf::from("/home/.config").into(),
+            PathBuf::from("/home/.local/share").into(),
+            PathBuf::from("/home/.local/state").into(),
+            PathBuf::from("/home/.cache").into(),
+        )
+    }
+
+    fn dot_map(name: &str, mode: LinkMode) -> DotMap {
+        DotMap::new(
+            PathBuf::from("/home/_/etc").join(name),
+            Destination::Home.locate(Target::new(name.into(), None, None, mode, None)),
+        )
+    }
+
+    fn test_fs() -> TestFs {
+        let mut fs = TestFs::new([
+            (PathBuf::from("/home/_/etc/vimrc"), TestFile::Regular),
+            (PathBuf::from("/home/_/etc/bashrc"), TestFile::Regular),
+            (PathBuf::from("/home/_/etc/gitconfig"), TestFile::Regular),
+            (PathBuf::from("/home/_/etc/profile"), TestFile::Regular),
+            (PathBuf::from(RC_FILE), TestFile::Regular),
+            (
+                PathBuf::from("/home/.vimrc"),
+                TestFile::Symlink(PathBuf::from("_/etc/vimrc")),
+            ),
+            // Broken, left over from a source that was renamed
+            (
+                PathBuf::from("/home/.bashrc"),
+                TestFile::Symlink(PathBuf::from("_/etc/bash_profile")),
+            ),
+            // In place, though not in the relative style sync would make
+            (
+                PathBuf::from("/home/.gitconfig"),
+                TestFile::Symlink(PathBuf::from("/home/_/etc/gitconfig")),
+            ),
+            (PathBuf::from("/home/.profile"), TestFile::Regular),
+        ]);
+        fs.inodes.insert(PathBuf::from("/home/_/etc/profile"), 7);
+        fs.inodes.insert(PathBuf::from("/home/.profile"), 7);
+        fs.contents
+            .insert(PathBuf::from(RC_FILE), b"repo:\n  location: _\n".to_vec());
+        fs
+    }
+
+    #[test]
+    fn test_relocate() {
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+        let linker = DotLinker::new(&fs, &fs, LinkStyle::Relative, None);
+        let link_creator = LinkCreator::new(&fs, &fs, &actions, None);
+        let environment = environment();
+        let (old, new) = (Path::new("/home/_"), Path::new("/home/src/dots"));
+
+        let in_place = links_in_place(
+            &fs,
+            &environment,
+            [
+                dot_map("vimrc", LinkMode::Symlink),
+                dot_map("bashrc", LinkMode::Symlink),
+                dot_map("gitconfig", LinkMode::Symlink),
+                dot_map("profile", LinkMode::Hardlink),
+            ],
+            old,
+        );
+        assert_eq!(
+            in_place,
+            vec![
+                dot_map("gitconfig", LinkMode::Symlink),
+                dot_map("vimrc", LinkMode::Symlink)
+            ]
+        );
+
+        let new_rc = relocated_rc(Rc::default(), "src/dots".into(), ConfigFormat::Yaml).unwrap();
+        move_repo(
+            &fs,
+            &actions,
+            (old, new),
+            Path::new(RC_FILE),
+            ("repo:\n  location: _\n", &new_rc),
+        )
+        .unwrap();
+        let report = rewrite_links(&linker, &link_creator, &environment, in_place, (old, new));
+        assert_eq!(report.failures(), 0);
+
+        let fs = actions.fs.borrow();
+        assert_eq!(
+            fs.read_link("/home/.vimrc").unwrap(),
+            PathBuf::from("src/dots/etc/vimrc")
+        );
+        assert_eq!(
+            fs.read_link("/home/.gitconfig").unwrap(),
+            PathBuf::from("src/dots/etc/gitconfig")
+        );
+        assert_eq!(
+            fs.read_link("/home/.bashrc").unwrap(),
+            PathBuf::from("_/etc/bash_profile")
+        );
+        assert_eq!(
+            fs.get_file(&PathBuf::from("/home/src/dots/etc/vimrc"))
+                .unwrap(),
+            TestFile::Regular
+        );
+        assert!(!fs.is_dir("/home/_"));
+        // Hard links stay the same file wherever the repo moves to
+        assert_eq!(
+            fs.inode("/home/.profile").unwrap(),
+            fs.inode("/home/src/dots/etc/profile").unwrap()
+        );
+        assert_eq!(
+            fs.read_file(RC_FILE).unwrap(),
+            b"repo:\n  location: src/dots\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_failed_move_restores_rc() {
+        let fs = test_fs();
+        let actions = TestActions::new(RefCell::new(test_fs()));
+
+        let moved = move_repo(
+            &fs,
+            &actions,
+            (Path::new("/home/missing"), Path::new("/home/dots")),
+            Path::new(RC_FILE),
+            ("repo:\n  location: _\n", "repo:\n  location: dots\n"),
+        );
+        assert!(moved.is_err());
+        assert_eq!(
+            actions.fs.borrow().read_file(RC_FILE).unwrap(),
+            b"repo:\n  location: _\n".to_vec()
+        );
+    }
+}
@@ -1,15 +1,43 @@
-use log::{error, info};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use log::{debug, info, warn};
 use thiserror::Error;
 
-use crate::app::{
-    cli::{Cli, Command},
-    types::App,
+use crate::{
+    app::{
+        cli::{Cli, Command},
+        logging::LogFile,
+        types::App,
+    },
+    components::{
+        dotzo::types::Dotzo,
+        environment::{
+            inference::DirInference,
+            types::{Home, StateDir},
+        },
+    },
+    util::lock::{Lock, LockError},
 };
 
 use super::{
+    adopt::{adopt_task, AdoptTaskError},
+    check_spec::{check_spec_task, CheckSpecTaskError},
+    clean::{clean_task, CleanTaskError},
+    completions::{completions_task, CompletionsTaskError},
+    doctor::{doctor_task, DoctorTaskError},
+    env::env_task,
     info::{info_task, InfoTaskError},
-    init::{init_task, InitTaskError},
+    init::{clone_task, init_task, load_task, InitTaskError},
+    pull::{pull_task, PullTaskError},
+    push::{push_task, PushTaskError},
+    relocate::{relocate_task, RelocateTaskError},
+    schema::{schema_task, SchemaTaskError},
+    status::{status_task, StatusTaskError},
     sync::{sync_task, SyncTaskError},
+    watch::{watch_task, WatchTaskError},
 };
 
 #[derive(Debug, Error)]
@@ -20,20 +48,165 @@ pub enum RunTaskError {
     #[error("Problem with the environment")]
     Sync(#[from] SyncTaskError),
 
+    #[error("Problem watching the repos")]
+    Watch(#[from] WatchTaskError),
+
     #[error("Problem with the environment")]
     Info(#[from] InfoTaskError),
+
+    #[error("Problem with the repo specs")]
+    CheckSpec(#[from] CheckSpecTaskError),
+
+    #[error("Problem generating the schema")]
+    Schema(#[from] SchemaTaskError),
+
+    #[error("Problem generating completions")]
+    Completions(#[from] CompletionsTaskError),
+
+    #[error("Problem getting the status")]
+    Status(#[from] StatusTaskError),
+
+    #[error("Problem pulling the repos")]
+    Pull(#[from] PullTaskError),
+
+    #[error("Problem pushing the repos")]
+    Push(#[from] PushTaskError),
+
+    #[error("Problem with the environment")]
+    Doctor(#[from] DoctorTaskError),
+
+    #[error("Problem cleaning up links")]
+    Clean(#[from] CleanTaskError),
+
+    #[error("Problem relocating the repo")]
+    Relocate(#[from] RelocateTaskError),
+
+    #[error("Problem adopting the file")]
+    Adopt(#[from] AdoptTaskError),
+
+    #[error("Problem locking out other runs")]
+    Lock(#[from] LockError),
 }
 
 pub type Result<T> = core::result::Result<T, RunTaskError>;
 
-pub fn run<'a, APP: App<'a>>(app: &'a APP, cli: &Cli) -> Result<()> {
-    info!("Initializing Dotzo");
-    let dotzo = init_task(app, cli)?;
+// The state directory when the environment can't be loaded
+fn fallback_state_dir<'a, APP: App<'a>>(app: &'a APP) -> Option<PathBuf> {
+    let inference = app.inference();
+    DirInference::<StateDir>::infer(inference)
+        .or_else(|| DirInference::<Home>::infer(inference).map(|home| home.join(".local/state")))
+}
+
+// Starts the log file below the state directory, only once it exists since making it is init's
+// job. A dry run creates nothing, so it only logs to a directory that is already there
+fn open_log(cli: &Cli, log_file: &LogFile, state: Option<&Path>) {
+    let dir = state
+        .filter(|state| state.is_dir())
+        .map(|state| state.join("dotzo/logs"))
+        .filter(|dir| !cli.dry_run || dir.is_dir());
+    match dir {
+        Some(dir) => {
+            if let Err(e) = log_file.open(&dir) {
+                warn!("Can't write the log file: {}", e);
+            }
+        }
+        None => {
+            debug!("No log directory to write to, so nothing is logged to a file");
+            log_file.close();
+        }
+    }
+}
+
+/// Locks out other runs that change the environment. Dry runs change nothing, so run alongside
+/// anything, and without a state directory there is nothing to lock yet
+pub fn lock_environment(cli: &Cli, dotzo: &Dotzo) -> core::result::Result<Option<Lock>, LockError> {
+    let state = dotzo.environment.state.as_ref();
+    if cli.dry_run {
+        return Ok(None);
+    }
+    if !state.is_dir() {
+        debug!("No state directory at {}, not locked yet", state.display());
+        return Ok(None);
+    }
+    Lock::acquire(&state.join("dotzo"), cli.wait).map(Some)
+}
+
+// Locks out other runs and makes the layout for the commands that need it. The lock is taken
+// before init changes anything, or on a first run once init has made the state directory for it,
+// and held until the command finishes. Watch takes it around each resync instead
+fn prepare<'a, APP: App<'a>>(
+    app: &'a APP,
+    cli: &Cli,
+    dotzo: Dotzo,
+) -> Result<(Dotzo, Option<Lock>)> {
+    let locks = cli.command.changes_files() && !matches!(cli.command, Command::Watch { .. });
+    let lock = match locks {
+        true => lock_environment(cli, &dotzo)?,
+        false => None,
+    };
 
+    let dotzo = match &cli.command {
+        Command::Init
+        | Command::Sync { .. }
+        | Command::Watch { .. }
+        | Command::Info
+        | Command::Pull => init_task(app, cli, dotzo)?,
+        _ => dotzo,
+    };
+
+    let lock = match lock {
+        None if locks => lock_environment(cli, &dotzo)?,
+        lock => lock,
+    };
+    Ok((dotzo, lock))
+}
+
+pub fn run<'a, APP: App<'a>>(app: &'a APP, cli: &Cli, log_file: &LogFile) -> Result<()> {
     info!("Running task: {:?}", cli.command);
-    match cli.command {
-        Command::Init => Ok(()),
-        Command::Sync => Ok(sync_task(app, cli, dotzo)?),
-        Command::Info => Ok(info_task(dotzo.environment)?),
+    let dotzo = match &cli.command {
+        Command::Schema { kind } => return Ok(schema_task(*kind)?),
+        Command::Completions { shell, dynamic } => return Ok(completions_task(*shell, *dynamic)?),
+        // Loads the environment itself so a failing home is one of its findings
+        Command::Doctor { fix } => {
+            open_log(cli, log_file, fallback_state_dir(app).as_deref());
+            return Ok(doctor_task(app, cli, *fix)?);
+        }
+        _ => match load_task(app, cli) {
+            Ok(dotzo) => dotzo,
+            Err(e) => {
+                // So the failure is still logged
+                open_log(cli, log_file, fallback_state_dir(app).as_deref());
+                return Err(e.into());
+            }
+        },
+    };
+    debug!("Environment: {:?}", dotzo.environment);
+    debug!("Repos: {:?}", dotzo.repos);
+
+    // Init makes the state directory on a first run, so the records so far are held until it is
+    // done, whether or not it worked
+    let state = dotzo.environment.state.as_ref().to_owned();
+    let prepared = prepare(app, cli, dotzo);
+    open_log(cli, log_file, Some(&state));
+    let (dotzo, _lock) = prepared?;
+
+    match &cli.command {
+        Command::Init => clone_task(app, cli, &dotzo)?,
+        Command::Sync { paths } => sync_task(app, cli, paths, dotzo)?,
+        Command::Watch { debounce } => {
+            watch_task(app, cli, Duration::from_millis(*debounce), dotzo)?
+        }
+        Command::Info => info_task(app, dotzo)?,
+        Command::Status { check } => status_task(app, cli, *check, dotzo)?,
+        Command::Pull => pull_task(app, cli, dotzo)?,
+        Command::Push { message } => push_task(app, message, dotzo)?,
+        Command::Adopt { path, into, .. } => adopt_task(app, cli, path, into.as_deref(), dotzo)?,
+        Command::Relocate { path } => relocate_task(app, cli, path, dotzo)?,
+        Command::Clean => clean_task(app, dotzo)?,
+        Command::Env { shell } => env_task(app, *shell, dotzo),
+        // Only reads the repo, so nothing in the environment is created
+        Command::CheckSpec => check_spec_task(app, cli, dotzo)?,
+        Command::Schema { .. } | Command::Completions { .. } | Command::Doctor { .. } => {}
     }
+    Ok(())
 }
@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+use crate::config::schema::SchemaKind;
+
+#[derive(Debug, Error)]
+pub enum SchemaTaskError {
+    #[error("Error serializing schema: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+pub type Result<T> = core::result::Result<T, SchemaTaskError>;
+
+pub fn schema_task(kind: SchemaKind) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&kind.schema())?);
+    Ok(())
+}
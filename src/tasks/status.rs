@@ -0,0 +1,112 @@
+use log::info;
+use thiserror::Error;
+
+use super::{clean::repo_dangling_links, info::print_repo_states};
+use crate::{
+    app::{cli::Cli, types::App},
+    components::{
+        dotzo::types::Dotzo,
+        linker::{
+            link::{DotLinker, DotLinkerError},
+            types::DotStatus,
+        },
+        repo::{
+            checks::structure::StructureCheckError as RepoStructureCheckError,
+            tree::{TreeTraverser, TreeTraverserError},
+        },
+    },
+    util::secret::VaultError,
+};
+
+#[derive(Debug, Error)]
+pub enum StatusTaskError {
+    #[error("Structure check failure: {0}")]
+    RepoStructure(#[from] RepoStructureCheckError),
+
+    #[error("Link error: {0}")]
+    Link(#[from] DotLinkerError),
+
+    #[error("Error traversing repo: {0}")]
+    Traversal(#[from] TreeTraverserError),
+
+    #[error("Secret key error: {0}")]
+    Vault(#[from] VaultError),
+
+    #[error("{0} links are not in place")]
+    Drift(usize),
+}
+
+pub type Result<T> = core::result::Result<T, StatusTaskError>;
+
+pub fn status_task<'a, APP: App<'a>>(
+    app: &'a APP,
+    cli: &Cli,
+    check: bool,
+    dotzo: Dotzo,
+) -> Result<()> {
+    let vault = dotzo.vault()?;
+    let linker = DotLinker::new(
+        app.metadata_checks(),
+        app.link_reader(),
+        dotzo.rc.link,
+        vault.as_ref(),
+    );
+    let traverser = TreeTraverser::new(
+        app.metadata_checks(),
+        app.directory_listing(),
+        cli.spec_strictness(),
+    );
+    let repo_checks = app.repo_structure_check();
+
+    info!("Checking the repositories");
+    for repo in &dotzo.repos {
+        repo_checks.check(repo)?;
+    }
+    print_repo_states(app.git(), &dotzo.repos);
+
+    info!("Getting mappings from the repositories.");
+    let mut dot_maps: Vec<_> = traverser
+        .traverse_layered(&dotzo.environment, &dotzo.repos)?
+        .dot_maps
+        .into_iter()
+        .collect();
+    dot_maps.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let show_repo = dotzo.repos.len() > 1;
+    let mut drifted = 0;
+    for (target, layered) in dot_maps {
+        let link = linker.create_link(&dotzo.environment, &layered.dot_map)?;
+        let status = linker.check(&link)?;
+        if status != DotStatus::Confirmed {
+            drifted += 1;
+        }
+        if show_repo {
+            println!(
+                "{:<12} {} [{}]",
+                status.to_string(),
+                target.display(),
+                layered.repo.display()
+            );
+        } else {
+            println!("{:<12} {}", status.to_string(), target.display());
+        }
+        for overridden in layered.overridden {
+            println!("{:<12}   overrides {}", "", overridden.display());
+        }
+    }
+
+    for dangling in repo_dangling_links(app, &dotzo) {
+        drifted += 1;
+        println!(
+            "{:<12} {} -> {}",
+            "dangling",
+            dangling.path.display(),
+            dangling.linked.display()
+        );
+    }
+
+    if check && drifted > 0 {
+        return Err(StatusTaskError::Drift(drifted));
+    }
+    Ok(())
+}
@@ -1,8 +1,17 @@
-use log::{error, info};
+use log::{error, info, warn};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    env::current_dir,
+    path::PathBuf,
+};
 use thiserror::Error;
 
 use crate::{
-    action::make_link::{LinkCreator, LinkCreatorError},
+    action::{
+        hook_runner::{HookKind, HookRunner, HookRunnerError},
+        link_selector::{LinkChanges, LinkPolicy, LinkSelector, LinkSelectorError},
+        make_link::{LinkChange, LinkCreator, LinkCreatorError},
+    },
     app::{cli::Cli, types::App},
     components::{
         dotzo::types::Dotzo,
@@ -10,19 +19,27 @@ use crate::{
         linker::{
             link::{DotLinker, DotLinkerError},
             reconciliation::{DotReconciliation, DotReconciliationError},
+            report::{LinkOutcome, SyncReport},
         },
         repo::{
             checks::structure::StructureCheckError as RepoStructureCheckError,
-            tree::{TreeTraverser, TreeTraverserError},
+            selection::PathSelection,
+            tree::{LayeredTree, TreeTraverser, TreeTraverserError},
+            types::Repo,
         },
     },
-    util::prompting::{Prompter, PrompterError},
+    config::rc::types::DirtyRepo,
+    util::{
+        error::reason,
+        git::{Error as GitError, Git},
+        secret::VaultError,
+    },
 };
 
 #[derive(Debug, Error)]
 pub enum SyncTaskError {
-    #[error("Prompt error")]
-    Prompt(#[from] PrompterError),
+    #[error("Link selection error: {0}")]
+    Selection(#[from] LinkSelectorError),
 
     #[error("Structure check failure: {0}")]
     Structure(#[from] StructureCheckError),
@@ -41,68 +58,329 @@ pub enum SyncTaskError {
 
     #[error("Error traversing repo: {0}")]
     Traversal(#[from] TreeTraverserError),
+
+    #[error("Hook error: {0}")]
+    Hook(#[from] HookRunnerError),
+
+    #[error("Git error: {0}")]
+    Git(#[from] GitError),
+
+    #[error("Repo {} has uncommitted changes", .0.display())]
+    DirtyRepo(PathBuf),
+
+    #[error("Nothing in the repos matches {}", .0.display())]
+    NoMatch(PathBuf),
+
+    #[error("{} holds the whole home or config directory, give a path below it", .0.display())]
+    TooBroad(PathBuf),
+
+    #[error("Secret key error: {0}")]
+    Vault(#[from] VaultError),
+
+    #[error("Io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{failed} of {total} link operations and {hooks} hooks failed")]
+    PartialFailure {
+        failed: usize,
+        total: usize,
+        hooks: usize,
+    },
 }
 
 pub type Result<T> = core::result::Result<T, SyncTaskError>;
 
-pub fn sync_task<'a, APP: App<'a>>(app: &'a APP, _cli: &Cli, dotzo: Dotzo) -> Result<()> {
-    // Components
-    let linker = DotLinker::new(app.metadata_checks(), app.link_reader());
-    let link_creator = LinkCreator::new(app.metadata_checks(), app.link_reader(), app.actions());
-    let traverser = TreeTraverser::new(app.metadata_checks(), app.directory_listing());
-    let prompting = app.prompter();
-    let checks = app.structure_check();
-    let repo_checks = app.repo_structure_check();
-
-    // Checks
+fn check_dirty<G: Git>(git: &G, repo: &Repo, policy: DirtyRepo) -> Result<()> {
+    if policy == DirtyRepo::Ignore {
+        return Ok(());
+    }
+
+    let state = match repo.state(git) {
+        Ok(state) => state,
+        Err(e) if policy == DirtyRepo::Refuse => return Err(e.into()),
+        Err(e) => {
+            warn!("Can't get git state of {}: {}", repo.path.display(), e);
+            return Ok(());
+        }
+    };
+
+    match policy {
+        _ if !state.is_dirty() => Ok(()),
+        DirtyRepo::Refuse => Err(SyncTaskError::DirtyRepo(repo.path.clone())),
+        _ => {
+            warn!(
+                "Repo {} has {} uncommitted changes under etc",
+                repo.path.display(),
+                state.dirty.len()
+            );
+            Ok(())
+        }
+    }
+}
+
+// Groups changes by the directory of their source, where their hooks are declared
+fn changes_by_dir(changes: LinkChanges) -> BTreeMap<PathBuf, LinkChanges> {
+    let mut by_dir: BTreeMap<PathBuf, LinkChanges> = BTreeMap::new();
+    for (change, link) in changes {
+        let dir = link
+            .source
+            .parent()
+            .map(ToOwned::to_owned)
+            .unwrap_or_default();
+        by_dir.entry(dir).or_default().push((change, link));
+    }
+    by_dir
+}
+
+/// Syncs every mapping in the repos, or only those selected by the given paths
+pub fn sync_task<'a, APP: App<'a>>(
+    app: &'a APP,
+    cli: &Cli,
+    paths: &[PathBuf],
+    dotzo: Dotzo,
+) -> Result<()> {
+    check_environment(app, &dotzo)?;
+    for repo in &dotzo.repos {
+        check_dirty(app.git(), repo, dotzo.rc.dirty_repo)?;
+    }
+    match sync_links(app, cli, cli.link_policy(), paths, &dotzo)? {
+        Some(report) => finish(report),
+        None => Ok(()),
+    }
+}
+
+/// Checks the environment and the repos are in shape to be synced
+pub(crate) fn check_environment<'a, APP: App<'a>>(app: &'a APP, dotzo: &Dotzo) -> Result<()> {
     info!("Checking the environment structure");
-    checks.check(&dotzo.environment)?;
+    app.structure_check().check(&dotzo.environment)?;
     info!("Environment structure checked");
 
-    info!("Checking the repository");
-    repo_checks.check(&dotzo.repo)?;
-    info!("Repository validated");
+    info!("Checking the repositories");
+    for repo in &dotzo.repos {
+        app.repo_structure_check().check(repo)?;
+    }
+    info!("Repositories validated");
+    Ok(())
+}
+
+/// Reconciles the selected mappings and applies the changes the policy and the user choose,
+/// with no report when every link was already in place
+pub(crate) fn sync_links<'a, APP: App<'a>>(
+    app: &'a APP,
+    cli: &Cli,
+    policy: LinkPolicy,
+    paths: &[PathBuf],
+    dotzo: &Dotzo,
+) -> Result<Option<SyncReport>> {
+    // Components
+    let vault = dotzo.vault()?;
+    let linker = DotLinker::new(
+        app.metadata_checks(),
+        app.link_reader(),
+        dotzo.rc.link,
+        vault.as_ref(),
+    );
+    let link_creator = LinkCreator::new(
+        app.metadata_checks(),
+        app.link_reader(),
+        app.actions(),
+        vault.as_ref(),
+    );
+    let traverser = TreeTraverser::new(
+        app.metadata_checks(),
+        app.directory_listing(),
+        cli.spec_strictness(),
+    );
+    let hook_runner = HookRunner::new(app.actions(), &dotzo.environment);
+    let link_selector = LinkSelector::new(app.prompter(), policy);
 
     // Get Mappings
-    info!("Getting mappings from the repository.");
-    let dot_maps = traverser.traverse(dotzo.repo.etc())?;
+    info!("Getting mappings from the repositories.");
+    let LayeredTree {
+        mut dot_maps,
+        hooks,
+    } = traverser.traverse_layered(&dotzo.environment, &dotzo.repos)?;
+    info!("Got {} mappings", dot_maps.len());
+
+    let roots = [
+        dotzo.environment.home.as_ref(),
+        dotzo.environment.config.as_ref(),
+    ];
+    let selection = PathSelection::new(paths, &current_dir()?, &dotzo.repos, &roots);
+    if let Some(broad) = selection.too_broad().into_iter().next() {
+        return Err(SyncTaskError::TooBroad(broad));
+    }
+    if !selection.is_empty() {
+        let total = dot_maps.len();
+        if let Some(unmatched) = selection.retain(&mut dot_maps).into_iter().next() {
+            return Err(SyncTaskError::NoMatch(unmatched));
+        }
+        info!("Selected {} of {} mappings", dot_maps.len(), total);
+    }
     let link_count = dot_maps.len();
-    info!("Got {} mappings", link_count);
 
     // Reconciliation
     info!("Doing mapping reconciliation.");
     let DotReconciliation {
-        confirmed, pending, ..
-    } = DotReconciliation::with_linker(&linker, &dotzo.environment, dot_maps.into_values())?;
+        confirmed,
+        pending,
+        clobber,
+        fix,
+        relink,
+        overwrite,
+        permissions,
+    } = DotReconciliation::with_linker(
+        &linker,
+        &dotzo.environment,
+        dot_maps.into_values().map(|layered| layered.dot_map),
+    )?;
 
     if confirmed.len() == link_count {
         info!(
             "Confirmed all {} links already correct. Everything is synced.",
             link_count,
         );
-        return Ok(());
+        return Ok(None);
     }
     info!(
         "Confirmed {} of {} links are already correct.",
         confirmed.len(),
         link_count
     );
+    info!(
+        "Found {} new links, {} files in the way, {} links to fix, {} stale hard links, {} \
+         changed secrets and {} with wrong permissions.",
+        pending.len(),
+        clobber.len(),
+        fix.len(),
+        relink.len(),
+        overwrite.len(),
+        permissions.len()
+    );
+
+    let changes: LinkChanges = pending
+        .into_iter()
+        .map(|link| (LinkChange::Create, link))
+        .chain(clobber.into_iter().map(|link| (LinkChange::Backup, link)))
+        .chain(fix.into_iter().map(|link| (LinkChange::Fix, link)))
+        .chain(relink.into_iter().map(|link| (LinkChange::Relink, link)))
+        .chain(
+            overwrite
+                .into_iter()
+                .map(|link| (LinkChange::Overwrite, link)),
+        )
+        .chain(
+            permissions
+                .into_iter()
+                .map(|link| (LinkChange::Permissions, link)),
+        )
+        .collect();
+    let proposed: Vec<(LinkChange, PathBuf)> = changes
+        .iter()
+        .map(|(change, link)| (*change, link.target.clone()))
+        .collect();
+    let changes = changes_by_dir(link_selector.select(changes)?);
 
-    if !pending.is_empty() {
-        info!("Can create {} of {} new links.", pending.len(), link_count);
-        let do_create_links =
-            prompting.confirm(format!("Create {} new links?", pending.len()), false)?;
-        // .with_help_message("This will create new dotfile links in home, .config, and other specified locations.")
+    let mut report = SyncReport::default();
+    let selected: HashSet<&PathBuf> = changes
+        .values()
+        .flatten()
+        .map(|(_, link)| &link.target)
+        .collect();
+    for (change, target) in &proposed {
+        if !selected.contains(target) {
+            report.record(target, LinkOutcome::Skipped(*change));
+        }
+    }
+    if changes.is_empty() {
+        info!("Will not change any links");
+        return Ok(Some(report));
+    }
 
-        if do_create_links {
-            info!("Confirmed: creating links");
-            for dot_link in pending {
-                link_creator.create(&dot_link)?;
+    for (dir, hooks) in changes
+        .keys()
+        .filter_map(|dir| Some((dir, hooks.get(dir)?)))
+    {
+        for kind in [HookKind::PreSync, HookKind::OnLink, HookKind::PostSync] {
+            for command in kind.commands(hooks) {
+                println!("Will run {} hook in {}: {}", kind, dir.display(), command);
             }
-        } else {
-            info!("Will not create links")
         }
     }
 
-    Ok(())
+    info!("Applying the selected links");
+    // Links in a directory whose pre_sync hook failed are not attempted
+    let mut blocked: HashMap<&PathBuf, String> = HashMap::new();
+    for dir in changes.keys() {
+        if let Some(dir_hooks) = hooks.get(dir) {
+            if let Err(e) = hook_runner.run(HookKind::PreSync, dir, dir_hooks, None) {
+                error!("{}", reason(&e));
+                blocked.insert(dir, reason(&e));
+            }
+        }
+    }
+
+    for (dir, dir_changes) in &changes {
+        if let Some(cause) = blocked.get(dir) {
+            for (change, dot_link) in dir_changes {
+                report.record(
+                    &dot_link.target,
+                    LinkOutcome::Failed(*change, cause.clone()),
+                );
+            }
+            continue;
+        }
+
+        let dir_hooks = hooks.get(dir);
+        let mut changed = false;
+        for (change, dot_link) in dir_changes {
+            match link_creator.apply(*change, dot_link) {
+                Ok(true) => {
+                    changed = true;
+                    report.record(&dot_link.target, LinkOutcome::Applied(*change));
+                    if let Some(dir_hooks) = dir_hooks {
+                        if let Err(e) =
+                            hook_runner.run(HookKind::OnLink, dir, dir_hooks, Some(dot_link))
+                        {
+                            error!("{}", reason(&e));
+                            report.record_hook_failure(reason(&e));
+                        }
+                    }
+                }
+                Ok(false) => report.record(&dot_link.target, LinkOutcome::Unchanged),
+                Err(e) => {
+                    error!(
+                        "Failed to {} {}: {}",
+                        change,
+                        dot_link.target.display(),
+                        reason(&e)
+                    );
+                    report.record(&dot_link.target, LinkOutcome::Failed(*change, reason(&e)));
+                }
+            }
+        }
+        if let (true, Some(dir_hooks)) = (changed, dir_hooks) {
+            if let Err(e) = hook_runner.run(HookKind::PostSync, dir, dir_hooks, None) {
+                error!("{}", reason(&e));
+                report.record_hook_failure(reason(&e));
+            }
+        }
+    }
+
+    Ok(Some(report))
+}
+
+// Prints the report, failing at the end if any operation did
+fn finish(report: SyncReport) -> Result<()> {
+    for line in report.lines() {
+        println!("{}", line);
+    }
+    if !report.has_failures() {
+        return Ok(());
+    }
+    Err(SyncTaskError::PartialFailure {
+        failed: report.failures(),
+        total: report.outcomes.len(),
+        hooks: report.hook_failures.len(),
+    })
 }
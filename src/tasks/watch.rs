@@ -0,0 +1,103 @@
+use std::{thread::sleep, time::Duration};
+
+use log::{error, info, warn};
+use thiserror::Error;
+
+use crate::{
+    action::link_selector::LinkPolicy,
+    app::{cli::Cli, types::App},
+    components::dotzo::types::Dotzo,
+    util::{error::reason, lock::LockError, watch::TreeWatcher},
+};
+
+use super::{
+    run::lock_environment,
+    sync::{check_environment, sync_links, SyncTaskError},
+};
+
+#[derive(Debug, Error)]
+pub enum WatchTaskError {
+    #[error("Sync error: {0}")]
+    Sync(#[from] SyncTaskError),
+
+    #[error("Error watching the repos: {0}")]
+    Watch(#[from] std::io::Error),
+
+    #[error("Lock error: {0}")]
+    Lock(#[from] LockError),
+}
+
+pub type Result<T> = core::result::Result<T, WatchTaskError>;
+
+// How long to wait before trying again when none of the repos can be watched, or another run
+// holds the lock
+const RETRY: Duration = Duration::from_secs(5);
+
+/// Resyncs whenever something under etc in the repos changes, until interrupted. New links
+/// are made without asking, files in the way are asked about as in sync
+pub fn watch_task<'a, APP: App<'a>>(
+    app: &'a APP,
+    cli: &Cli,
+    debounce: Duration,
+    dotzo: Dotzo,
+) -> Result<()> {
+    // The dirty repo policy is not checked, since the repos are being edited
+    check_environment(app, &dotzo)?;
+    let policy = LinkPolicy {
+        create: true,
+        ..cli.link_policy()
+    };
+    let mut watcher = TreeWatcher::new()?;
+
+    let mut resync = true;
+    loop {
+        // A repo that can't be watched is skipped, and tried again on the next round
+        for repo in &dotzo.repos {
+            if let Err(e) = watcher.watch(&repo.etc()) {
+                warn!("Can't watch {}: {}", repo.etc().display(), e);
+            }
+        }
+
+        if resync {
+            // Held only while resyncing, so other runs can go in between changes
+            let lock = match lock_environment(cli, &dotzo) {
+                Err(e @ LockError::Held { .. }) => {
+                    warn!("Resyncing later: {}", e);
+                    sleep(RETRY);
+                    continue;
+                }
+                lock => lock?,
+            };
+            // A broken spec mid edit is reported and watching carries on
+            match sync_links(app, cli, policy, &[], &dotzo) {
+                Ok(None) => {}
+                Ok(Some(report)) => {
+                    for line in report.lines() {
+                        println!("{}", line);
+                    }
+                    if report.has_failures() {
+                        error!(
+                            "{} link operations and {} hooks failed",
+                            report.failures(),
+                            report.hook_failures.len()
+                        );
+                    }
+                }
+                Err(e) => error!("Sync failed: {}", reason(&e)),
+            }
+            drop(lock);
+        }
+
+        // Nothing would ever wake the wait, so look for the repos again in a while
+        if watcher.is_empty() {
+            resync = false;
+            sleep(RETRY);
+            continue;
+        }
+        info!("Watching {} repos for changes", dotzo.repos.len());
+        for path in watcher.wait(debounce)? {
+            info!("Changed {}", path.display());
+        }
+        resync = true;
+    }
+}
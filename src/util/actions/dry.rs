@@ -1,15 +1,36 @@
-use derive_more::derive::Constructor;
-use std::{io::ErrorKind, path::Path};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    ffi::OsStr,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
 
 use log::info;
 
 use crate::util::fs::MetadataChecks;
 
-use super::types::{Actions, Result};
+use super::types::{Actions, Error, Result};
 
-#[derive(Debug, Constructor)]
+#[derive(Debug)]
 pub struct DryActions<'a, MC: MetadataChecks> {
     metadata_checks: &'a MC,
+
+    // Paths that would have been moved or removed, so they no longer count as existing
+    cleared: RefCell<HashSet<PathBuf>>,
+}
+
+impl<'a, MC: MetadataChecks> DryActions<'a, MC> {
+    pub fn new(metadata_checks: &'a MC) -> Self {
+        Self {
+            metadata_checks,
+            cleared: Default::default(),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata_checks.exists(path) && !self.cleared.borrow().contains(path)
+    }
 }
 
 impl<MC: MetadataChecks> Actions for DryActions<'_, MC> {
@@ -20,7 +41,7 @@ impl<MC: MetadataChecks> Actions for DryActions<'_, MC> {
     }
 
     fn symlink(&self, target: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<()> {
-        if self.metadata_checks.exists(&target) {
+        if self.exists(target.as_ref()) {
             Err(std::io::Error::new(
                 ErrorKind::AlreadyExists,
                 "Target already exists",
@@ -34,4 +55,83 @@ impl<MC: MetadataChecks> Actions for DryActions<'_, MC> {
             Ok(())
         }
     }
+
+    fn hard_link(&self, target: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<()> {
+        if self.exists(target.as_ref()) {
+            Err(Error::from_io_kind(ErrorKind::AlreadyExists))
+        } else {
+            info!(
+                "DRY-RUN: Would have created hard link from {} to {}",
+                target.as_ref().display(),
+                path.as_ref().display()
+            );
+            Ok(())
+        }
+    }
+
+    fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        let from = from.as_ref();
+        info!(
+            "DRY-RUN: Would have moved {} to {}",
+            from.display(),
+            to.as_ref().display()
+        );
+        self.cleared.borrow_mut().insert(from.to_owned());
+        Ok(())
+    }
+
+    fn write_file(&self, path: impl AsRef<Path>, contents: &str) -> Result<()> {
+        info!(
+            "DRY-RUN: Would have written {}:\n{}",
+            path.as_ref().display(),
+            contents
+        );
+        Ok(())
+    }
+
+    fn write_private(&self, path: impl AsRef<Path>, _contents: &[u8]) -> Result<()> {
+        let path = path.as_ref();
+        if self.exists(path) {
+            return Err(Error::from_io_kind(ErrorKind::AlreadyExists));
+        }
+        // The contents are secret, so unlike write_file they are not logged
+        info!(
+            "DRY-RUN: Would have written private file {}",
+            path.display()
+        );
+        Ok(())
+    }
+
+    fn set_permissions(&self, path: impl AsRef<Path>, mode: u32) -> Result<()> {
+        info!(
+            "DRY-RUN: Would have set permissions of {} to {:04o}",
+            path.as_ref().display(),
+            mode
+        );
+        Ok(())
+    }
+
+    fn remove_link(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !self.metadata_checks.is_symlink(path) {
+            return Err(Error::from_io_kind(ErrorKind::InvalidInput));
+        }
+        info!("DRY-RUN: Would have removed symlink {}", path.display());
+        self.cleared.borrow_mut().insert(path.to_owned());
+        Ok(())
+    }
+
+    fn run_command(
+        &self,
+        command: &str,
+        dir: impl AsRef<Path>,
+        _env: &[(&str, &OsStr)],
+    ) -> Result<()> {
+        info!(
+            "DRY-RUN: Would have run `{}` in {}",
+            command,
+            dir.as_ref().display()
+        );
+        Ok(())
+    }
 }
@@ -1,9 +1,19 @@
 use derive_more::derive::Constructor;
-use std::{fs::create_dir_all, os::unix::fs::symlink, path::Path};
+use std::{
+    ffi::OsStr,
+    fs::{
+        create_dir_all, hard_link, remove_file, rename, set_permissions, symlink_metadata, write,
+        OpenOptions, Permissions,
+    },
+    io::{ErrorKind, Write},
+    os::unix::fs::{symlink, OpenOptionsExt, PermissionsExt},
+    path::Path,
+    process::Command,
+};
 
 use log::info;
 
-use super::types::{Actions, Result};
+use super::types::{Actions, Error, Result};
 
 #[derive(Debug, Constructor)]
 pub struct StandardActions {}
@@ -11,17 +21,94 @@ pub struct StandardActions {}
 impl Actions for StandardActions {
     fn make_dir(&self, path: impl AsRef<Path>) -> Result<()> {
         let path = path.as_ref();
-        info!("Creating directory: {}", path.display());
+        info!(path:% = path.display(); "Creating directory: {}", path.display());
         create_dir_all(path)?;
         Ok(())
     }
 
     fn symlink(&self, target: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<()> {
         info!(
+            path:% = target.as_ref().display(), link:% = path.as_ref().display();
             "Creating symlink from {} to {}",
             target.as_ref().display(),
             path.as_ref().display()
         );
         Ok(symlink(path, target)?)
     }
+
+    fn hard_link(&self, target: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<()> {
+        info!(
+            path:% = target.as_ref().display(), link:% = path.as_ref().display();
+            "Creating hard link from {} to {}",
+            target.as_ref().display(),
+            path.as_ref().display()
+        );
+        Ok(hard_link(path, target)?)
+    }
+
+    fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        info!(
+            path:% = from.as_ref().display(), to:% = to.as_ref().display();
+            "Moving {} to {}",
+            from.as_ref().display(),
+            to.as_ref().display()
+        );
+        Ok(rename(from, to)?)
+    }
+
+    fn write_file(&self, path: impl AsRef<Path>, contents: &str) -> Result<()> {
+        let path = path.as_ref();
+        info!(path:% = path.display(); "Writing {}", path.display());
+        Ok(write(path, contents)?)
+    }
+
+    fn write_private(&self, path: impl AsRef<Path>, contents: &[u8]) -> Result<()> {
+        let path = path.as_ref();
+        info!(path:% = path.display(); "Writing private file {}", path.display());
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)?;
+        Ok(file.write_all(contents)?)
+    }
+
+    fn set_permissions(&self, path: impl AsRef<Path>, mode: u32) -> Result<()> {
+        let path = path.as_ref();
+        info!(path:% = path.display(); "Setting permissions of {} to {:04o}", path.display(), mode);
+        Ok(set_permissions(path, Permissions::from_mode(mode))?)
+    }
+
+    fn remove_link(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !symlink_metadata(path)?.is_symlink() {
+            return Err(Error::from_io_kind(ErrorKind::InvalidInput));
+        }
+        info!(path:% = path.display(); "Removing symlink {}", path.display());
+        Ok(remove_file(path)?)
+    }
+
+    fn run_command(
+        &self,
+        command: &str,
+        dir: impl AsRef<Path>,
+        env: &[(&str, &OsStr)],
+    ) -> Result<()> {
+        let dir = dir.as_ref();
+        info!(path:% = dir.display(); "Running `{}` in {}", command, dir.display());
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(dir)
+            .envs(env.iter().copied())
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::CommandFailed {
+                command: command.to_owned(),
+                status,
+            })
+        }
+    }
 }
@@ -1,6 +1,9 @@
-use std::{cell::RefCell, io::ErrorKind, path::Path};
-
-use derive_more::derive::Constructor;
+use std::{
+    cell::RefCell,
+    ffi::{OsStr, OsString},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
 
 use crate::util::{
     actions::types::{Actions, Error, Result},
@@ -10,9 +13,23 @@ use crate::util::{
     },
 };
 
-#[derive(Debug, Constructor)]
+pub type CommandEnv = Vec<(String, OsString)>;
+
+#[derive(Debug)]
 pub struct TestActions {
     pub fs: RefCell<TestFs>,
+
+    // Commands run, with the directory they ran in and their environment
+    pub commands: RefCell<Vec<(String, PathBuf, CommandEnv)>>,
+}
+
+impl TestActions {
+    pub fn new(fs: RefCell<TestFs>) -> Self {
+        Self {
+            fs,
+            commands: Default::default(),
+        }
+    }
 }
 
 impl Actions for TestActions {
@@ -33,4 +50,106 @@ impl Actions for TestActions {
             Ok(())
         }
     }
+
+    fn hard_link(&self, target: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<()> {
+        let mut fs = self.fs.borrow_mut();
+        let target = target.as_ref();
+        let inode = fs.inode(path)?;
+
+        if fs.exists(target) {
+            Err(Error::from_io_kind(ErrorKind::AlreadyExists))
+        } else {
+            fs.add_file(target.to_owned(), TestFile::Regular);
+            fs.inodes.insert(target.to_owned(), inode);
+            Ok(())
+        }
+    }
+
+    fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        let mut fs = self.fs.borrow_mut();
+        let (from, to) = (from.as_ref(), to.as_ref());
+        if !fs.exists(from) && !fs.is_dir(from) {
+            return Err(Error::from_io_kind(ErrorKind::NotFound));
+        }
+
+        // Directories move with everything under them
+        let mut moved: Vec<PathBuf> = fs
+            .files
+            .keys()
+            .filter(|path| path.starts_with(from))
+            .cloned()
+            .collect();
+        moved.sort();
+        for path in moved {
+            let file = fs.remove_file(&path)?;
+            let new = to.join(path.strip_prefix(from).unwrap_or(&path));
+            if let Some(mode) = fs.modes.remove(&path) {
+                fs.modes.insert(new.clone(), mode);
+            }
+            if let Some(inode) = fs.inodes.remove(&path) {
+                fs.inodes.insert(new.clone(), inode);
+            }
+            if let Some(contents) = fs.contents.remove(&path) {
+                fs.contents.insert(new.clone(), contents);
+            }
+            fs.add_file(new, file);
+        }
+        fs.tree.retain(|path, _| !path.starts_with(from));
+        if let Some(siblings) = from.parent().and_then(|parent| fs.tree.get_mut(parent)) {
+            siblings.remove(from);
+        }
+        Ok(())
+    }
+
+    fn write_file(&self, path: impl AsRef<Path>, contents: &str) -> Result<()> {
+        let mut fs = self.fs.borrow_mut();
+        fs.add_file(path.as_ref().to_owned(), TestFile::Regular);
+        fs.contents
+            .insert(path.as_ref().to_owned(), contents.as_bytes().to_owned());
+        Ok(())
+    }
+
+    fn write_private(&self, path: impl AsRef<Path>, contents: &[u8]) -> Result<()> {
+        let mut fs = self.fs.borrow_mut();
+        let path = path.as_ref().to_owned();
+        if fs.exists(&path) {
+            return Err(Error::from_io_kind(ErrorKind::AlreadyExists));
+        }
+        fs.add_file(path.clone(), TestFile::Regular);
+        fs.modes.insert(path.clone(), 0o600);
+        fs.contents.insert(path, contents.to_owned());
+        Ok(())
+    }
+
+    fn set_permissions(&self, path: impl AsRef<Path>, mode: u32) -> Result<()> {
+        let mut fs = self.fs.borrow_mut();
+        let path = fs.follow_links(path)?;
+        fs.modes.insert(path, mode);
+        Ok(())
+    }
+
+    fn remove_link(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut fs = self.fs.borrow_mut();
+        if !fs.is_symlink(&path) {
+            return Err(Error::from_io_kind(ErrorKind::InvalidInput));
+        }
+        fs.remove_file(path.as_ref())?;
+        Ok(())
+    }
+
+    fn run_command(
+        &self,
+        command: &str,
+        dir: impl AsRef<Path>,
+        env: &[(&str, &OsStr)],
+    ) -> Result<()> {
+        let env = env
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_os_string()))
+            .collect();
+        self.commands
+            .borrow_mut()
+            .push((command.to_owned(), dir.as_ref().to_owned(), env));
+        Ok(())
+    }
 }
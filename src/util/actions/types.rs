@@ -1,9 +1,12 @@
-use std::{io::ErrorKind, path::Path};
+use std::{ffi::OsStr, io::ErrorKind, path::Path, process::ExitStatus};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("IO error")]
     Io(#[from] std::io::Error),
+
+    #[error("Command `{command}` failed: {status}")]
+    CommandFailed { command: String, status: ExitStatus },
 }
 
 impl Error {
@@ -17,4 +20,21 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub trait Actions {
     fn make_dir(&self, path: impl AsRef<Path>) -> Result<()>;
     fn symlink(&self, target: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<()>;
+    // Makes target another name for the file at path
+    fn hard_link(&self, target: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<()>;
+    fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()>;
+    // Replaces the contents of the file, creating it if needed
+    fn write_file(&self, path: impl AsRef<Path>, contents: &str) -> Result<()>;
+    // Creates a file only the user can read and write, failing if anything is at the path
+    fn write_private(&self, path: impl AsRef<Path>, contents: &[u8]) -> Result<()>;
+    // Sets the permission bits, following links
+    fn set_permissions(&self, path: impl AsRef<Path>, mode: u32) -> Result<()>;
+    // Fails without touching the path if it is not a symlink
+    fn remove_link(&self, path: impl AsRef<Path>) -> Result<()>;
+    fn run_command(
+        &self,
+        command: &str,
+        dir: impl AsRef<Path>,
+        env: &[(&str, &OsStr)],
+    ) -> Result<()>;
 }
@@ -0,0 +1,15 @@
+use std::error::Error;
+
+/// Describes an error with each of its causes not already in its message
+pub fn reason(error: &(dyn Error + 'static)) -> String {
+    let mut reason = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let message = cause.to_string();
+        if !reason.contains(&message) {
+            reason = format!("{}: {}", reason, message);
+        }
+        source = cause.source();
+    }
+    reason
+}
@@ -1,4 +1,11 @@
-use std::path::{Path, PathBuf};
+use std::{
+    ffi::CString,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{MetadataExt, PermissionsExt},
+    },
+    path::{Component, Path, PathBuf},
+};
 
 use derive_more::derive::Constructor;
 
@@ -12,12 +19,35 @@ pub trait MetadataChecks {
     fn is_file(&self, path: impl AsRef<Path>) -> bool;
     fn is_symlink(&self, path: impl AsRef<Path>) -> bool;
     fn exists(&self, path: impl AsRef<Path>) -> bool;
+    // Permission bits, following links
+    fn mode(&self, path: impl AsRef<Path>) -> std::io::Result<u32>;
+    // Id of the filesystem holding the path, following links
+    fn device(&self, path: impl AsRef<Path>) -> std::io::Result<u64>;
+    // Inode number, which hard links to the same file share, following links
+    fn inode(&self, path: impl AsRef<Path>) -> std::io::Result<u64>;
+    // Whether this process may write to the path, as the kernel decides for its user and groups
+    fn is_writable(&self, path: impl AsRef<Path>) -> bool;
 
     fn is_real_dir(&self, path: impl AsRef<Path>) -> bool {
         self.is_dir(path.as_ref()) && !self.is_symlink(path.as_ref())
     }
 }
 
+/// Resolves `.` and `..` without touching the filesystem
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normal.pop();
+            }
+            other => normal.push(other),
+        }
+    }
+    normal
+}
+
 pub trait DirectoryListing {
     type Iter: DirEntryIterator;
     fn read_dir(&self, path: impl AsRef<Path>) -> std::io::Result<Self::Iter>;
@@ -26,6 +56,8 @@ pub trait DirectoryListing {
 pub trait LinkReader {
     fn read_link(&self, path: impl AsRef<Path>) -> std::io::Result<PathBuf>;
     fn canonicalize(&self, path: impl AsRef<Path>) -> std::io::Result<PathBuf>;
+    // Contents of the file, following links, for comparing copies such as secrets
+    fn read_file(&self, path: impl AsRef<Path>) -> std::io::Result<Vec<u8>>;
 }
 
 pub trait FsRead: MetadataChecks + DirectoryListing + LinkReader {}
@@ -50,6 +82,26 @@ impl MetadataChecks for StandardFsRead {
     fn exists(&self, path: impl AsRef<Path>) -> bool {
         path.as_ref().exists()
     }
+
+    fn mode(&self, path: impl AsRef<Path>) -> std::io::Result<u32> {
+        Ok(path.as_ref().metadata()?.permissions().mode() & 0o7777)
+    }
+
+    fn device(&self, path: impl AsRef<Path>) -> std::io::Result<u64> {
+        Ok(path.as_ref().metadata()?.dev())
+    }
+
+    fn inode(&self, path: impl AsRef<Path>) -> std::io::Result<u64> {
+        Ok(path.as_ref().metadata()?.ino())
+    }
+
+    fn is_writable(&self, path: impl AsRef<Path>) -> bool {
+        let Ok(path) = CString::new(path.as_ref().as_os_str().as_bytes()) else {
+            return false;
+        };
+        // SAFETY: the path is a nul terminated string that outlives the call
+        unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+    }
 }
 
 impl DirectoryListing for StandardFsRead {
@@ -71,15 +123,49 @@ impl LinkReader for StandardFsRead {
     fn canonicalize(&self, path: impl AsRef<Path>) -> std::io::Result<PathBuf> {
         path.as_ref().canonicalize()
     }
+
+    fn read_file(&self, path: impl AsRef<Path>) -> std::io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
 }
 
 #[cfg(test)]
 pub mod testing {
     use std::collections::{HashMap, HashSet};
+    use std::hash::{DefaultHasher, Hash, Hasher};
     use std::io::{Error, ErrorKind, Result};
 
     use super::*;
 
+    /// A directory under the system temp dir for tests on the real filesystem, removed when
+    /// dropped so a failing test cleans up too
+    #[derive(Debug)]
+    pub struct ScratchDir(PathBuf);
+
+    impl ScratchDir {
+        /// The name must be unique among the tests, which run in parallel
+        pub fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("dotzo-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl std::ops::Deref for ScratchDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for ScratchDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
     #[derive(Debug, PartialEq, Eq, Hash, Clone)]
     pub enum TestFile {
         Regular,
@@ -91,6 +177,14 @@ pub mod testing {
     pub struct TestFs {
         pub tree: HashMap<PathBuf, HashSet<PathBuf>>,
         pub files: HashMap<PathBuf, TestFile>,
+        // Modes other than 0o755 for directories and 0o644 for files
+        pub modes: HashMap<PathBuf, u32>,
+        // Devices other than 0
+        pub devices: HashMap<PathBuf, u64>,
+        // Inodes shared through hard links, others are unique to the path
+        pub inodes: HashMap<PathBuf, u64>,
+        // Contents of regular files other than empty
+        pub contents: HashMap<PathBuf, Vec<u8>>,
     }
 
     impl TestFs {
@@ -109,7 +203,7 @@ pub mod testing {
         pub fn add_file(&mut self, path: PathBuf, file: TestFile) {
             // TODO: Normalize path
             if let TestFile::Directory = file {
-                self.tree.insert(path.clone(), Default::default());
+                self.tree.entry(path.clone()).or_default();
             }
             self.add_parents(&path);
             self.files.insert(path, file);
@@ -126,6 +220,18 @@ pub mod testing {
             fs
         }
 
+        pub fn remove_file(&mut self, path: &Path) -> std::io::Result<TestFile> {
+            let file = self
+                .files
+                .remove(path)
+                .ok_or_else(|| Error::from(ErrorKind::NotFound))?;
+            self.tree.remove(path);
+            if let Some(siblings) = path.parent().and_then(|p| self.tree.get_mut(p)) {
+                siblings.remove(path);
+            }
+            Ok(file)
+        }
+
         pub fn get_file(&self, path: &PathBuf) -> std::io::Result<TestFile> {
             self.files
                 .get(path)
@@ -135,6 +241,10 @@ pub mod testing {
 
         pub fn follow_links<P: AsRef<Path>>(&self, path: P) -> std::io::Result<PathBuf> {
             let path = path.as_ref().to_path_buf();
+            // Parents of added files exist as directories without an entry of their own
+            if !self.files.contains_key(&path) && self.tree.contains_key(&path) {
+                return Ok(path);
+            }
             let mut visited: HashSet<PathBuf> = Default::default();
             visited.insert(path.to_path_buf());
 
@@ -171,6 +281,34 @@ pub mod testing {
         fn exists(&self, path: impl AsRef<Path>) -> bool {
             self.files.contains_key(path.as_ref())
         }
+
+        fn mode(&self, path: impl AsRef<Path>) -> Result<u32> {
+            let path = self.follow_links(path)?;
+            Ok(match self.modes.get(&path) {
+                Some(mode) => *mode,
+                None if self.is_dir(&path) => 0o755,
+                None => 0o644,
+            })
+        }
+
+        fn device(&self, path: impl AsRef<Path>) -> Result<u64> {
+            let path = self.follow_links(path)?;
+            Ok(self.devices.get(&path).copied().unwrap_or_default())
+        }
+
+        fn inode(&self, path: impl AsRef<Path>) -> Result<u64> {
+            let path = self.follow_links(path)?;
+            Ok(self.inodes.get(&path).copied().unwrap_or_else(|| {
+                let mut hasher = DefaultHasher::new();
+                path.hash(&mut hasher);
+                hasher.finish()
+            }))
+        }
+
+        // There is only the one user, so the owner bit decides
+        fn is_writable(&self, path: impl AsRef<Path>) -> bool {
+            self.mode(path).is_ok_and(|mode| mode & 0o200 != 0)
+        }
     }
 
     impl DirectoryListing for TestFs {
@@ -187,12 +325,23 @@ pub mod testing {
 
     impl LinkReader for TestFs {
         fn read_link(&self, path: impl AsRef<Path>) -> std::io::Result<PathBuf> {
-            self.follow_links(path)
+            match self.get_file(&path.as_ref().to_path_buf())? {
+                TestFile::Symlink(linked) => Ok(linked),
+                _ => Err(Error::from(ErrorKind::InvalidInput)),
+            }
         }
 
         fn canonicalize(&self, path: impl AsRef<Path>) -> std::io::Result<PathBuf> {
             // TODO: Complete
             Ok(path.as_ref().to_path_buf())
         }
+
+        fn read_file(&self, path: impl AsRef<Path>) -> std::io::Result<Vec<u8>> {
+            let path = self.follow_links(path)?;
+            match self.get_file(&path)? {
+                TestFile::Regular => Ok(self.contents.get(&path).cloned().unwrap_or_default()),
+                _ => Err(Error::from(ErrorKind::InvalidInput)),
+            }
+        }
     }
 }
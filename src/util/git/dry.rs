@@ -0,0 +1,62 @@
+use derive_more::derive::Constructor;
+use log::info;
+use std::path::{Path, PathBuf};
+
+use super::types::{BranchInfo, Git, Result, StatusEntry};
+
+/// Reads through the given git, but only logs changes
+#[derive(Debug, Constructor)]
+pub struct DryGit<'a, G: Git> {
+    git: &'a G,
+}
+
+impl<G: Git> Git for DryGit<'_, G> {
+    fn clone_repo(&self, remote: impl AsRef<str>, path: impl AsRef<Path>) -> Result<()> {
+        info!(
+            "DRY-RUN: Would have cloned {} into {}",
+            remote.as_ref(),
+            path.as_ref().display()
+        );
+        Ok(())
+    }
+
+    fn pull(&self, repo: impl AsRef<Path>) -> Result<()> {
+        info!(
+            "DRY-RUN: Would have pulled into {}",
+            repo.as_ref().display()
+        );
+        Ok(())
+    }
+
+    fn status(&self, repo: impl AsRef<Path>) -> Result<Vec<StatusEntry>> {
+        self.git.status(repo)
+    }
+
+    fn branch(&self, repo: impl AsRef<Path>) -> Result<BranchInfo> {
+        self.git.branch(repo)
+    }
+
+    fn prefix(&self, repo: impl AsRef<Path>) -> Result<PathBuf> {
+        self.git.prefix(repo)
+    }
+
+    fn commit(
+        &self,
+        repo: impl AsRef<Path>,
+        pathspec: impl AsRef<Path>,
+        message: impl AsRef<str>,
+    ) -> Result<()> {
+        info!(
+            "DRY-RUN: Would have committed {} in {} with message {:?}",
+            pathspec.as_ref().display(),
+            repo.as_ref().display(),
+            message.as_ref()
+        );
+        Ok(())
+    }
+
+    fn push(&self, repo: impl AsRef<Path>) -> Result<()> {
+        info!("DRY-RUN: Would have pushed {}", repo.as_ref().display());
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod dry;
+pub mod standard;
+pub mod types;
+
+pub use dry::DryGit;
+pub use standard::StandardGit;
+pub use types::{BranchInfo, Error, Git, StatusEntry};
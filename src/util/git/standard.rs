@@ -0,0 +1,213 @@
+use derive_more::derive::Constructor;
+use log::{debug, info};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::types::{BranchInfo, Error, Git, Result, StatusEntry};
+
+/// Runs the git command line tool
+#[derive(Debug, Constructor)]
+pub struct StandardGit {}
+
+impl StandardGit {
+    fn run<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(&self, args: I) -> Result<String> {
+        let mut command = Command::new("git");
+        command.args(args);
+        debug!("Running {:?}", command);
+
+        let output = command.output()?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(Error::Failed {
+                command: command
+                    .get_args()
+                    .map(|a| a.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            })
+        }
+    }
+
+    fn run_in<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
+        &self,
+        repo: &Path,
+        args: I,
+    ) -> Result<String> {
+        let mut all_args = vec![OsStr::new("-C").to_owned(), repo.as_os_str().to_owned()];
+        all_args.extend(args.into_iter().map(|a| a.as_ref().to_owned()));
+        self.run(all_args)
+    }
+}
+
+impl Git for StandardGit {
+    fn clone_repo(&self, remote: impl AsRef<str>, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        info!("Cloning {} into {}", remote.as_ref(), path.display());
+        self.run([
+            OsStr::new("clone"),
+            OsStr::new(remote.as_ref()),
+            path.as_os_str(),
+        ])?;
+        Ok(())
+    }
+
+    fn pull(&self, repo: impl AsRef<Path>) -> Result<()> {
+        let repo = repo.as_ref();
+        info!("Pulling into {}", repo.display());
+        self.run_in(repo, ["pull", "--ff-only"])?;
+        Ok(())
+    }
+
+    fn status(&self, repo: impl AsRef<Path>) -> Result<Vec<StatusEntry>> {
+        let output = self.run_in(
+            repo.as_ref(),
+            ["status", "--porcelain=v1", "-z", "--untracked-files=all"],
+        )?;
+        Ok(StatusEntry::parse_porcelain(&output))
+    }
+
+    fn branch(&self, repo: impl AsRef<Path>) -> Result<BranchInfo> {
+        let output = self.run_in(
+            repo.as_ref(),
+            [
+                "status",
+                "--porcelain=v2",
+                "--branch",
+                "-z",
+                "--untracked-files=no",
+            ],
+        )?;
+        Ok(BranchInfo::parse_porcelain_v2(&output))
+    }
+
+    fn prefix(&self, repo: impl AsRef<Path>) -> Result<PathBuf> {
+        let output = self.run_in(repo.as_ref(), ["rev-parse", "--show-prefix"])?;
+        Ok(PathBuf::from(output.trim_end_matches('\n')))
+    }
+
+    fn commit(
+        &self,
+        repo: impl AsRef<Path>,
+        pathspec: impl AsRef<Path>,
+        message: impl AsRef<str>,
+    ) -> Result<()> {
+        let repo = repo.as_ref();
+        let pathspec = pathspec.as_ref().as_os_str();
+        info!("Committing changes in {}", repo.display());
+        self.run_in(
+            repo,
+            [
+                OsStr::new("add"),
+                OsStr::new("-A"),
+                OsStr::new("--"),
+                pathspec,
+            ],
+        )?;
+        self.run_in(
+            repo,
+            [
+                OsStr::new("commit"),
+                OsStr::new("-m"),
+                OsStr::new(message.as_ref()),
+                OsStr::new("--"),
+                pathspec,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn push(&self, repo: impl AsRef<Path>) -> Result<()> {
+        let repo = repo.as_ref();
+        info!("Pushing {}", repo.display());
+        self.run_in(repo, ["push"])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::fs::testing::ScratchDir;
+
+    fn clone_bare(git: &StandardGit, dir: &Path) -> (PathBuf, PathBuf) {
+        let bare = dir.join("remote.git");
+        git.run([OsStr::new("init"), OsStr::new("--bare"), bare.as_os_str()])
+            .unwrap();
+        let cloned = dir.join("_");
+        git.clone_repo(bare.to_str().unwrap(), &cloned).unwrap();
+        for (key, value) in [("user.name", "dotzo"), ("user.email", "dotzo@example.com")] {
+            git.run_in(&cloned, ["config", key, value]).unwrap();
+        }
+        (bare, cloned)
+    }
+
+    #[test]
+    fn test_clone_local_bare_repo() {
+        let dir = ScratchDir::new("git-clone");
+        let (_, cloned) = clone_bare(&StandardGit::new(), &dir);
+        assert!(cloned.join(".git").is_dir());
+    }
+
+    #[test]
+    fn test_clone_missing_remote() {
+        let dir = ScratchDir::new("git-missing");
+        let result = StandardGit::new().clone_repo("/does/not/exist.git", dir.join("_"));
+        assert!(matches!(result, Err(Error::Failed { .. })));
+    }
+
+    #[test]
+    fn test_prefix_of_nested_dir() {
+        let dir = ScratchDir::new("git-prefix");
+        let git = StandardGit::new();
+        let (_, cloned) = clone_bare(&git, &dir);
+        assert_eq!(git.prefix(&cloned).unwrap(), PathBuf::new());
+
+        let nested = cloned.join("dots");
+        std::fs::create_dir_all(nested.join("etc")).unwrap();
+        std::fs::write(nested.join("etc/vimrc"), "set number\n").unwrap();
+        assert_eq!(git.prefix(&nested).unwrap(), PathBuf::from("dots/"));
+        let status = git.status(&nested).unwrap();
+        assert_eq!(status[0].path, PathBuf::from("dots/etc/vimrc"));
+    }
+
+    #[test]
+    fn test_commit_push_and_pull() {
+        let dir = ScratchDir::new("git-roundtrip");
+        let git = StandardGit::new();
+        let (bare, cloned) = clone_bare(&git, &dir);
+
+        std::fs::create_dir_all(cloned.join("etc")).unwrap();
+        std::fs::write(cloned.join("etc/vimrc"), "set number\n").unwrap();
+        std::fs::write(cloned.join("notes"), "not in etc\n").unwrap();
+        let status = git.status(&cloned).unwrap();
+        assert_eq!(status.len(), 2);
+
+        git.commit(&cloned, "etc", "Add vimrc").unwrap();
+        let status = git.status(&cloned).unwrap();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].path, PathBuf::from("notes"));
+        git.push(&cloned).unwrap();
+        let branch = git.branch(&cloned).unwrap();
+        assert!(branch.upstream.is_some());
+        assert_eq!((branch.ahead, branch.behind), (0, 0));
+
+        let other = dir.join("other");
+        git.clone_repo(bare.to_str().unwrap(), &other).unwrap();
+        assert!(other.join("etc/vimrc").is_file());
+
+        std::fs::write(cloned.join("etc/vimrc"), "set nonumber\n").unwrap();
+        git.commit(&cloned, "etc", "Update vimrc").unwrap();
+        assert_eq!(git.branch(&cloned).unwrap().ahead, 1);
+        git.push(&cloned).unwrap();
+        git.pull(&other).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(other.join("etc/vimrc")).unwrap(),
+            "set nonumber\n"
+        );
+    }
+}
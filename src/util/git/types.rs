@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("IO error running git: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("git {command} failed: {stderr}")]
+    Failed { command: String, stderr: String },
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// A single entry from `git status --porcelain`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusEntry {
+    // The two letter status code, index then working tree
+    pub code: String,
+
+    // Path relative to the repo root
+    pub path: PathBuf,
+}
+
+impl StatusEntry {
+    pub fn is_conflict(&self) -> bool {
+        matches!(
+            self.code.as_str(),
+            "DD" | "AU" | "UD" | "UA" | "DU" | "AA" | "UU"
+        )
+    }
+
+    /// Parses the output of `git status --porcelain=v1 -z`
+    pub fn parse_porcelain(output: &str) -> Vec<Self> {
+        let mut entries = Vec::new();
+        let mut fields = output.split('\0').filter(|f| !f.is_empty());
+        while let Some(field) = fields.next() {
+            // The code is followed by a single space, the path may start with more
+            let Some((code, path)) = field
+                .split_at_checked(2)
+                .and_then(|(code, rest)| Some((code, rest.strip_prefix(' ')?)))
+            else {
+                continue;
+            };
+            // Renames and copies are followed by the original path
+            if code.starts_with(['R', 'C']) {
+                fields.next();
+            }
+            entries.push(StatusEntry {
+                code: code.to_owned(),
+                path: PathBuf::from(path),
+            });
+        }
+        entries
+    }
+}
+
+/// Branch headers from `git status --porcelain=v2 --branch`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BranchInfo {
+    // None when the head is detached
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+impl BranchInfo {
+    /// Parses the headers of `git status --porcelain=v2 --branch -z`
+    pub fn parse_porcelain_v2(output: &str) -> Self {
+        let mut info = Self::default();
+        for header in output.split('\0').filter_map(|f| f.strip_prefix("# ")) {
+            match header.split_once(' ') {
+                Some(("branch.head", "(detached)")) => info.head = None,
+                Some(("branch.head", head)) => info.head = Some(head.to_owned()),
+                Some(("branch.upstream", upstream)) => info.upstream = Some(upstream.to_owned()),
+                Some(("branch.ab", counts)) => {
+                    for count in counts.split(' ') {
+                        if let Some(ahead) = count.strip_prefix('+') {
+                            info.ahead = ahead.parse().unwrap_or_default();
+                        } else if let Some(behind) = count.strip_prefix('-') {
+                            info.behind = behind.parse().unwrap_or_default();
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        info
+    }
+}
+
+pub trait Git {
+    fn clone_repo(&self, remote: impl AsRef<str>, path: impl AsRef<Path>) -> Result<()>;
+
+    /// Fetches and fast-forwards the current branch
+    fn pull(&self, repo: impl AsRef<Path>) -> Result<()>;
+
+    fn status(&self, repo: impl AsRef<Path>) -> Result<Vec<StatusEntry>>;
+
+    fn branch(&self, repo: impl AsRef<Path>) -> Result<BranchInfo>;
+
+    /// Path of the repo dir relative to the top of its work tree, which status paths start from
+    fn prefix(&self, repo: impl AsRef<Path>) -> Result<PathBuf>;
+
+    /// Commits all changes under the pathspec
+    fn commit(
+        &self,
+        repo: impl AsRef<Path>,
+        pathspec: impl AsRef<Path>,
+        message: impl AsRef<str>,
+    ) -> Result<()>;
+
+    fn push(&self, repo: impl AsRef<Path>) -> Result<()>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain() {
+        let output =
+            " M etc/vimrc\0?? etc/new file\0R  etc/renamed\0etc/original\0UU etc/tmux.conf\0";
+        let entries = StatusEntry::parse_porcelain(output);
+        let paths: Vec<_> = entries.iter().map(|e| e.path.to_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            vec!["etc/vimrc", "etc/new file", "etc/renamed", "etc/tmux.conf"]
+        );
+        let conflicts: Vec<_> = entries.iter().map(StatusEntry::is_conflict).collect();
+        assert_eq!(conflicts, vec![false, false, false, true]);
+    }
+
+    #[test]
+    fn test_parse_porcelain_leading_space() {
+        let entries = StatusEntry::parse_porcelain("?? etc/ spaced\0 M  leading\0");
+        let paths: Vec<_> = entries.iter().map(|e| e.path.to_str().unwrap()).collect();
+        assert_eq!(paths, vec!["etc/ spaced", " leading"]);
+    }
+
+    #[test]
+    fn test_parse_porcelain_clean() {
+        assert!(StatusEntry::parse_porcelain("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_branch_info() {
+        let output = [
+            "# branch.oid 1234abcd",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "1 .M N... 100644 100644 100644 abc abc etc/vimrc",
+        ]
+        .join("\0");
+        let expected = BranchInfo {
+            head: Some("main".into()),
+            upstream: Some("origin/main".into()),
+            ahead: 2,
+            behind: 1,
+        };
+        assert_eq!(expected, BranchInfo::parse_porcelain_v2(&output));
+    }
+
+    #[test]
+    fn test_parse_branch_info_detached() {
+        let output = "# branch.oid 1234abcd\0# branch.head (detached)\0";
+        assert_eq!(
+            BranchInfo::default(),
+            BranchInfo::parse_porcelain_v2(output)
+        );
+    }
+}
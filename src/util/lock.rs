@@ -0,0 +1,129 @@
+use std::{
+    fs::{create_dir, File, OpenOptions, TryLockError},
+    io::{ErrorKind, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LockError {
+    #[error(
+        "Another dotzo is running{}, holding {}",
+        pid.map(|pid| format!(" as pid {}", pid)).unwrap_or_default(),
+        path.display()
+    )]
+    Held { pid: Option<u32>, path: PathBuf },
+
+    #[error("Can't use the lock file {}: {error}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+}
+
+pub type Result<T> = core::result::Result<T, LockError>;
+
+/// Keeps other dotzo runs from changing the environment until it is dropped. The lock is an
+/// flock on the file, so the kernel releases it however the process ends
+#[derive(Debug)]
+pub struct Lock {
+    _file: File,
+}
+
+// The pid written by the holder, which may not have written it yet
+fn holder(file: &mut File) -> Option<u32> {
+    let mut pid = String::new();
+    file.read_to_string(&mut pid).ok()?;
+    pid.trim().parse().ok()
+}
+
+impl Lock {
+    /// Locks dotzo.lock in the directory, which is made if its parent exists. While another
+    /// process holds it, fails or with wait blocks until it is released
+    pub fn acquire(dir: &Path, wait: bool) -> Result<Self> {
+        let path = dir.join("dotzo.lock");
+        let io_error = |error| LockError::Io {
+            path: path.clone(),
+            error,
+        };
+        match create_dir(dir) {
+            Err(e) if e.kind() != ErrorKind::AlreadyExists => return Err(io_error(e)),
+            _ => {}
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(io_error)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let pid = holder(&mut file);
+                if !wait {
+                    return Err(LockError::Held { pid, path });
+                }
+                match pid {
+                    Some(pid) => info!("Waiting for dotzo pid {} to finish", pid),
+                    None => info!("Waiting for the other dotzo to finish"),
+                }
+                file.lock().map_err(io_error)?;
+            }
+            Err(TryLockError::Error(e)) => return Err(io_error(e)),
+        }
+
+        // Only read to name the holder when another run is refused
+        file.set_len(0).map_err(io_error)?;
+        file.rewind().map_err(io_error)?;
+        write!(file, "{}", std::process::id()).map_err(io_error)?;
+        Ok(Self { _file: file })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{read_to_string, write};
+
+    use super::*;
+    use crate::util::fs::testing::ScratchDir;
+
+    #[test]
+    fn test_held_until_dropped() {
+        let dir = ScratchDir::new("lock-held");
+        let lock = Lock::acquire(&dir.join("dotzo"), false).unwrap();
+        assert!(matches!(
+            Lock::acquire(&dir.join("dotzo"), false),
+            Err(LockError::Held { pid: Some(pid), .. }) if pid == std::process::id()
+        ));
+
+        drop(lock);
+        drop(Lock::acquire(&dir.join("dotzo"), false).unwrap());
+    }
+
+    #[test]
+    fn test_ignores_left_pid() {
+        let dir = ScratchDir::new("lock-left");
+        write(dir.join("dotzo.lock"), u32::MAX.to_string()).unwrap();
+
+        let _lock = Lock::acquire(&dir, false).unwrap();
+        assert_eq!(
+            read_to_string(dir.join("dotzo.lock")).unwrap(),
+            std::process::id().to_string()
+        );
+    }
+
+    #[test]
+    fn test_missing_parent() {
+        let dir = ScratchDir::new("lock-missing");
+        assert!(matches!(
+            Lock::acquire(&dir.join("state/dotzo"), false),
+            Err(LockError::Io { .. })
+        ));
+        assert!(!dir.join("state").exists());
+    }
+}
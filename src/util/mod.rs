@@ -1,4 +1,10 @@
 pub mod actions;
 pub mod dir;
+pub mod error;
 pub mod fs;
+pub mod git;
+pub mod lock;
 pub mod prompting;
+pub mod secret;
+pub mod shell;
+pub mod watch;
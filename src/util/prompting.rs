@@ -1,27 +1,218 @@
+use std::io::{stdin, IsTerminal};
+
 use derive_more::derive::Constructor;
-use inquire::Confirm;
+use inquire::{Confirm, InquireError, MultiSelect};
+use log::info;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum PrompterError {
     #[error("IO error")]
     General(Box<dyn core::error::Error + Send + Sync>),
+
+    #[error("Can't ask \"{0}\" without a terminal, use --yes or --no-input")]
+    NotInteractive(String),
+
+    #[error("Prompt cancelled")]
+    Cancelled,
+}
+
+impl From<InquireError> for PrompterError {
+    fn from(error: InquireError) -> Self {
+        match error {
+            // Esc and Ctrl-C answer no to everything
+            InquireError::OperationCanceled | InquireError::OperationInterrupted => {
+                PrompterError::Cancelled
+            }
+            e => PrompterError::General(Box::new(e)),
+        }
+    }
 }
 
 pub type Result<T> = core::result::Result<T, PrompterError>;
 
 pub trait Prompter {
     fn confirm(&self, message: impl AsRef<str>, default: bool) -> Result<bool>;
+
+    /// Returns the indices of the chosen options, with `defaults` chosen to start with
+    fn multi_select(
+        &self,
+        message: impl AsRef<str>,
+        options: &[String],
+        defaults: &[usize],
+    ) -> Result<Vec<usize>>;
 }
 
 #[derive(Debug, Constructor)]
 pub struct InquirePrompter {}
 
+// Fails straight away rather than waiting on input that will never come
+fn check_interactive(message: &str) -> Result<()> {
+    if stdin().is_terminal() {
+        Ok(())
+    } else {
+        Err(PrompterError::NotInteractive(message.to_owned()))
+    }
+}
+
 impl Prompter for InquirePrompter {
     fn confirm(&self, message: impl AsRef<str>, default: bool) -> Result<bool> {
+        check_interactive(message.as_ref())?;
         Confirm::new(message.as_ref())
             .with_default(default)
             .prompt()
-            .map_err(|e| PrompterError::General(Box::new(e)))
+            .map_err(PrompterError::from)
+    }
+
+    fn multi_select(
+        &self,
+        message: impl AsRef<str>,
+        options: &[String],
+        defaults: &[usize],
+    ) -> Result<Vec<usize>> {
+        check_interactive(message.as_ref())?;
+        MultiSelect::new(message.as_ref(), options.to_vec())
+            .with_default(defaults)
+            .raw_prompt()
+            .map(|chosen| chosen.into_iter().map(|option| option.index).collect())
+            .map_err(PrompterError::from)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    Interactive,
+    // Every confirmation is accepted, selections keep their defaults
+    AssumeYes,
+    // Every question takes its default answer
+    Defaults,
+}
+
+/// Answers prompts itself unless the input mode is interactive
+#[derive(Debug, Constructor)]
+pub struct ModePrompter<'a, PR: Prompter> {
+    prompter: &'a PR,
+    mode: InputMode,
+}
+
+impl<PR: Prompter> Prompter for ModePrompter<'_, PR> {
+    fn confirm(&self, message: impl AsRef<str>, default: bool) -> Result<bool> {
+        let answer = match self.mode {
+            InputMode::Interactive => return self.prompter.confirm(message, default),
+            InputMode::AssumeYes => true,
+            InputMode::Defaults => default,
+        };
+        info!("{} {}", message.as_ref(), if answer { "yes" } else { "no" });
+        Ok(answer)
+    }
+
+    fn multi_select(
+        &self,
+        message: impl AsRef<str>,
+        options: &[String],
+        defaults: &[usize],
+    ) -> Result<Vec<usize>> {
+        if self.mode == InputMode::Interactive {
+            return self.prompter.multi_select(message, options, defaults);
+        }
+        info!("{}", message.as_ref());
+        for &i in defaults {
+            info!("  {}", options[i]);
+        }
+        Ok(defaults.to_vec())
+    }
+}
+
+#[cfg(test)]
+pub mod testing {
+    use std::{cell::RefCell, collections::VecDeque};
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Answer {
+        Confirm(bool),
+        // Indices to choose, regardless of the defaults
+        Select(Vec<usize>),
+    }
+
+    /// Answers prompts from a script, failing on any prompt it has no matching answer for
+    #[derive(Debug, Default)]
+    pub struct ScriptedPrompter {
+        answers: RefCell<VecDeque<Answer>>,
+        pub asked: RefCell<Vec<String>>,
+    }
+
+    impl ScriptedPrompter {
+        pub fn new<I: IntoIterator<Item = Answer>>(answers: I) -> Self {
+            Self {
+                answers: RefCell::new(answers.into_iter().collect()),
+                asked: Default::default(),
+            }
+        }
+
+        fn next(&self, message: &str) -> Result<Answer> {
+            self.asked.borrow_mut().push(message.to_owned());
+            self.answers.borrow_mut().pop_front().ok_or_else(|| {
+                PrompterError::General(format!("Unscripted prompt: {}", message).into())
+            })
+        }
+    }
+
+    impl Prompter for ScriptedPrompter {
+        fn confirm(&self, message: impl AsRef<str>, _default: bool) -> Result<bool> {
+            match self.next(message.as_ref())? {
+                Answer::Confirm(answer) => Ok(answer),
+                other => Err(PrompterError::General(
+                    format!("Expected a confirm answer, got {:?}", other).into(),
+                )),
+            }
+        }
+
+        fn multi_select(
+            &self,
+            message: impl AsRef<str>,
+            _options: &[String],
+            _defaults: &[usize],
+        ) -> Result<Vec<usize>> {
+            match self.next(message.as_ref())? {
+                Answer::Select(chosen) => Ok(chosen),
+                other => Err(PrompterError::General(
+                    format!("Expected a selection answer, got {:?}", other).into(),
+                )),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{testing::ScriptedPrompter, *};
+
+    #[test]
+    fn test_mode_prompter_answers_without_asking() {
+        let scripted = ScriptedPrompter::new([]);
+        let options = ["a".to_string(), "b".to_string()];
+
+        let yes = ModePrompter::new(&scripted, InputMode::AssumeYes);
+        assert!(yes.confirm("Continue?", false).unwrap());
+        assert_eq!(yes.multi_select("Pick", &options, &[1]).unwrap(), vec![1]);
+
+        let defaults = ModePrompter::new(&scripted, InputMode::Defaults);
+        assert!(!defaults.confirm("Continue?", false).unwrap());
+        assert!(defaults.confirm("Continue?", true).unwrap());
+        assert!(scripted.asked.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_cancel_is_not_a_general_error() {
+        assert!(matches!(
+            InquireError::OperationCanceled.into(),
+            PrompterError::Cancelled
+        ));
+        assert!(matches!(
+            InquireError::OperationInterrupted.into(),
+            PrompterError::Cancelled
+        ));
     }
 }
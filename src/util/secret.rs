@@ -0,0 +1,98 @@
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use age::x25519::Identity;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum VaultError {
+    #[error("Can't read the secret key {}: {error}", path.display())]
+    Key {
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+
+    #[error("No age identity in the secret key {}", .0.display())]
+    NoIdentity(PathBuf),
+
+    #[error("Decryption error: {0}")]
+    Decrypt(#[from] age::DecryptError),
+
+    #[error("Encryption error: {0}")]
+    Encrypt(#[from] age::EncryptError),
+}
+
+pub type Result<T> = core::result::Result<T, VaultError>;
+
+/// Decrypts secrets from the repos, and encrypts new ones, with an age identity
+pub struct Vault {
+    identity: Identity,
+}
+
+impl std::fmt::Debug for Vault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vault")
+            .field("recipient", &self.identity.to_public().to_string())
+            .finish()
+    }
+}
+
+impl Vault {
+    pub fn new(identity: Identity) -> Self {
+        Self { identity }
+    }
+
+    /// Loads the first identity in a key file, as written by `age-keygen`
+    pub fn load(path: &Path) -> Result<Self> {
+        let keys = read_to_string(path).map_err(|error| VaultError::Key {
+            path: path.to_owned(),
+            error,
+        })?;
+        keys.lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .find_map(|line| line.parse().ok())
+            .map(Self::new)
+            .ok_or_else(|| VaultError::NoIdentity(path.to_owned()))
+    }
+
+    pub fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>> {
+        Ok(age::decrypt(&self.identity, encrypted)?)
+    }
+
+    /// Encrypts to the identity's own recipient, armored so it diffs as text in the repo
+    pub fn encrypt(&self, contents: &[u8]) -> Result<String> {
+        Ok(age::encrypt_and_armor(
+            &self.identity.to_public(),
+            contents,
+        )?)
+    }
+}
+
+/// Hash to compare a decrypted secret with an installed copy
+pub fn digest(contents: &[u8]) -> Vec<u8> {
+    Sha256::digest(contents).to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let vault = Vault::new(Identity::generate());
+        let encrypted = vault.encrypt(b"token").unwrap();
+        assert!(encrypted.starts_with("-----BEGIN AGE ENCRYPTED FILE-----"));
+        assert_eq!(vault.decrypt(encrypted.as_bytes()).unwrap(), b"token");
+
+        let other = Vault::new(Identity::generate());
+        assert!(matches!(
+            other.decrypt(encrypted.as_bytes()),
+            Err(VaultError::Decrypt(_))
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+use std::{env, path::Path};
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// Guesses the shell from $SHELL, falling back to bash
+    pub fn from_env() -> Self {
+        env::var_os("SHELL")
+            .as_deref()
+            .and_then(|shell| Path::new(shell).file_name())
+            .and_then(|name| Self::from_str(&name.to_string_lossy(), true).ok())
+            .unwrap_or(Shell::Bash)
+    }
+
+    fn quote(self, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("'{}'", value.replace('\'', r"'\''")),
+            Shell::Fish => format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'")),
+        }
+    }
+
+    /// A line setting and exporting a variable
+    pub fn export(self, name: &str, value: impl AsRef<Path>) -> String {
+        let value = self.quote(&value.as_ref().to_string_lossy());
+        match self {
+            Shell::Bash | Shell::Zsh => format!("export {}={}", name, value),
+            Shell::Fish => format!("set -gx {} {}", name, value),
+        }
+    }
+
+    /// A line putting the directories, in order, at the front of PATH
+    pub fn prepend_path<P: AsRef<Path>>(self, dirs: impl IntoIterator<Item = P>) -> String {
+        let dirs: Vec<String> = dirs
+            .into_iter()
+            .map(|dir| self.quote(&dir.as_ref().to_string_lossy()))
+            .collect();
+        match self {
+            Shell::Bash | Shell::Zsh => format!("export PATH={}:\"$PATH\"", dirs.join(":")),
+            Shell::Fish => format!("set -gx PATH {} $PATH", dirs.join(" ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_export_quotes_value() {
+        assert_eq!(
+            Shell::Bash.export("DOTZO_REPO", "/home/it's/_"),
+            r"export DOTZO_REPO='/home/it'\''s/_'"
+        );
+        assert_eq!(
+            Shell::Fish.export("DOTZO_REPO", "/home/it's/_"),
+            r"set -gx DOTZO_REPO '/home/it\'s/_'"
+        );
+    }
+
+    #[test]
+    fn test_prepend_path() {
+        let dirs = ["/home/_/bin", "/home/team/bin"];
+        assert_eq!(
+            Shell::Zsh.prepend_path(dirs),
+            r#"export PATH='/home/_/bin':'/home/team/bin':"$PATH""#
+        );
+        assert_eq!(
+            Shell::Fish.prepend_path(dirs),
+            "set -gx PATH '/home/_/bin' '/home/team/bin' $PATH"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    thread::sleep,
+    time::Duration,
+};
+
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use walkdir::WalkDir;
+
+/// Watches directory trees with inotify, which only watches single directories
+pub struct TreeWatcher {
+    inotify: Inotify,
+    dirs: HashMap<WatchDescriptor, PathBuf>,
+    buffer: Vec<u8>,
+}
+
+impl TreeWatcher {
+    pub fn new() -> std::io::Result<Self> {
+        Ok(Self {
+            inotify: Inotify::init()?,
+            dirs: HashMap::new(),
+            buffer: vec![0; 4096],
+        })
+    }
+
+    /// Watches the directory and every directory below it, so calling it again picks up
+    /// directories created since
+    pub fn watch(&mut self, root: &Path) -> std::io::Result<()> {
+        let mask = WatchMask::CREATE | WatchMask::DELETE | WatchMask::MODIFY | WatchMask::MOVE;
+        for entry in WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|entry| entry.file_type().is_dir())
+        {
+            let dir = entry?.into_path();
+            let descriptor = self.inotify.watches().add(&dir, mask)?;
+            self.dirs.insert(descriptor, dir);
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dirs.is_empty()
+    }
+
+    // Adds the paths of any events waiting, returning how many there were
+    fn read(&mut self, block: bool, changed: &mut BTreeSet<PathBuf>) -> std::io::Result<usize> {
+        let Self {
+            inotify,
+            dirs,
+            buffer,
+        } = self;
+        let events = match block {
+            true => inotify.read_events_blocking(buffer),
+            false => match inotify.read_events(buffer) {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(0),
+                events => events,
+            },
+        }?;
+
+        let mut count = 0;
+        for event in events {
+            count += 1;
+            // Sent once a watched directory is gone
+            if event.mask.contains(EventMask::IGNORED) {
+                dirs.remove(&event.wd);
+            } else if let Some(dir) = dirs.get(&event.wd) {
+                changed.insert(
+                    event
+                        .name
+                        .map_or_else(|| dir.clone(), |name| dir.join(name)),
+                );
+            }
+        }
+        Ok(count)
+    }
+
+    /// Blocks until something changes, then until nothing has changed for the debounce
+    /// interval, returning every path changed meanwhile
+    pub fn wait(&mut self, debounce: Duration) -> std::io::Result<BTreeSet<PathBuf>> {
+        let mut changed = BTreeSet::new();
+        self.read(true, &mut changed)?;
+        loop {
+            sleep(debounce);
+            if self.read(false, &mut changed)? == 0 {
+                return Ok(changed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, thread};
+
+    use super::*;
+    use crate::util::fs::testing::ScratchDir;
+
+    #[test]
+    fn test_wait_coalesces_changes() {
+        let dir = ScratchDir::new("watch-coalesce");
+        let mut watcher = TreeWatcher::new().unwrap();
+        watcher.watch(&dir).unwrap();
+
+        fs::write(dir.join("vimrc"), "set number\n").unwrap();
+        let writer = {
+            let dir = dir.to_path_buf();
+            thread::spawn(move || {
+                sleep(Duration::from_millis(50));
+                fs::create_dir(dir.join("tmux")).unwrap();
+                fs::write(dir.join("tmux/tmux.conf"), "set -g mouse on\n").unwrap();
+            })
+        };
+        let changed = watcher.wait(Duration::from_millis(300)).unwrap();
+        writer.join().unwrap();
+        // The new directory was not watched yet, so the file in it only shows as the directory
+        assert_eq!(
+            changed,
+            BTreeSet::from([dir.join("tmux"), dir.join("vimrc")])
+        );
+
+        watcher.watch(&dir).unwrap();
+        fs::write(dir.join("tmux/tmux.conf"), "set -g mouse off\n").unwrap();
+        let changed = watcher.wait(Duration::from_millis(100)).unwrap();
+        assert_eq!(changed, BTreeSet::from([dir.join("tmux/tmux.conf")]));
+    }
+
+    #[test]
+    fn test_watch_missing_dir() {
+        let dir = ScratchDir::new("watch-missing");
+        let mut watcher = TreeWatcher::new().unwrap();
+        assert!(watcher.watch(&dir.join("etc")).is_err());
+        assert!(watcher.is_empty());
+    }
+}
@@ -1,5 +1,5 @@
 use derive_more::Constructor;
-use log::{debug, error};
+use log::debug;
 use std::path::Path;
 use thiserror::Error;
 
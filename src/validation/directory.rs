@@ -1,5 +1,4 @@
 use derive_more::Constructor;
-use log::error;
 use std::path::Path;
 use thiserror::Error;
 